@@ -0,0 +1,121 @@
+//! `vect-sale.toml`: cluster, signer and the sale being operated.
+//!
+//! ```toml
+//! rpc_url = "https://api.mainnet-beta.solana.com"
+//! keypair = "~/.config/solana/mainnet-deployer.json"   # optional with --unsigned
+//! commitment = "confirmed"
+//!
+//! [sale]
+//! authority = "..."   # defaults to the keypair's pubkey
+//! vect_mint = "..."
+//! usdc_mint = "..."
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub rpc_url: String,
+    /// Keypair that signs and pays for transactions. Leave it out on a
+    /// machine that only builds `--unsigned` transactions.
+    pub keypair: Option<String>,
+    #[serde(default = "default_commitment")]
+    pub commitment: String,
+    pub sale: SaleConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SaleConfig {
+    /// Sale authority; the `[b"sale", authority]` PDA is derived from it.
+    pub authority: Option<String>,
+    pub vect_mint: Option<String>,
+    pub usdc_mint: Option<String>,
+}
+
+fn default_commitment() -> String {
+    "confirmed".to_string()
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading config {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("parsing config {}", path.display()))
+    }
+
+    pub fn keypair_path(&self) -> Option<PathBuf> {
+        self.keypair.as_deref().map(expand_home)
+    }
+}
+
+impl SaleConfig {
+    pub fn authority(&self) -> Result<Option<Pubkey>> {
+        self.authority.as_deref().map(|key| parse_pubkey("sale.authority", key)).transpose()
+    }
+
+    pub fn vect_mint(&self) -> Result<Pubkey> {
+        required("sale.vect_mint", &self.vect_mint)
+    }
+
+    pub fn usdc_mint(&self) -> Result<Pubkey> {
+        required("sale.usdc_mint", &self.usdc_mint)
+    }
+}
+
+fn required(field: &str, value: &Option<String>) -> Result<Pubkey> {
+    let value = value
+        .as_deref()
+        .with_context(|| format!("`{field}` is not set in the config"))?;
+    parse_pubkey(field, value)
+}
+
+fn parse_pubkey(field: &str, value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).with_context(|| format!("`{field}` is not a valid pubkey: {value}"))
+}
+
+/// Expand a leading `~/` the way the Solana CLI does.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_documented_layout() {
+        let config: Config = toml::from_str(
+            r#"
+            rpc_url = "http://127.0.0.1:8899"
+            keypair = "~/.config/solana/id.json"
+
+            [sale]
+            vect_mint = "So11111111111111111111111111111111111111112"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.commitment, "confirmed");
+        assert_eq!(config.sale.authority().unwrap(), None);
+        assert!(config.sale.vect_mint().is_ok());
+        assert!(config.sale.usdc_mint().is_err());
+        assert!(!config.keypair_path().unwrap().starts_with("~"));
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(toml::from_str::<Config>(
+            "rpc_url = \"x\"\nkeypair = \"y\"\nprogram = \"z\"\n[sale]\n"
+        )
+        .is_err());
+    }
+}
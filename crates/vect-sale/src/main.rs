@@ -0,0 +1,992 @@
+//! `vect-sale`: one admin tool for the vesting_sale program.
+//!
+//! Every instruction has a subcommand of the same name. Cluster, signer and
+//! sale come from a TOML config (see `config.rs`); `--dry-run` simulates
+//! instead of sending and `--output json` prints machine-readable results.
+//! `--unsigned` prints the transaction for offline or multisig signing
+//! instead, `--squads` only its instructions for a multisig proposal (see
+//! `offline.rs`).
+
+mod config;
+mod offline;
+mod rpc;
+
+use std::path::{Path, PathBuf};
+
+use anchor_lang::AccountDeserialize;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::TokenAccount;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_system_interface::instruction as system_instruction;
+use vectaiproj_client::vectaiproj::{USDC_DECIMALS, VECT_DECIMALS};
+use vectaiproj_client::{instructions, pda, SaleState};
+
+use config::Config;
+use rpc::Rpc;
+
+#[derive(Parser)]
+#[command(name = "vect-sale", version, about = "Operate a vesting_sale deployment")]
+struct Cli {
+    /// Config file
+    #[arg(long, short, env = "VECT_SALE_CONFIG", default_value = "vect-sale.toml", global = true)]
+    config: PathBuf,
+
+    /// Keypair file, overriding the config
+    #[arg(long, global = true)]
+    keypair: Option<String>,
+
+    /// RPC URL, overriding the config
+    #[arg(long, global = true)]
+    url: Option<String>,
+
+    /// Simulate the transaction instead of sending it
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    #[arg(long, value_enum, default_value_t = Output::Text, global = true)]
+    output: Output,
+
+    /// Print the unsigned transaction instead of signing and sending it
+    #[arg(long, global = true, conflicts_with = "dry_run", help_heading = "Offline signing")]
+    unsigned: bool,
+
+    /// Fee payer (default: the keypair, or with --unsigned the first signer)
+    #[arg(long, global = true, help_heading = "Offline signing")]
+    fee_payer: Option<Pubkey>,
+
+    /// Durable nonce account; its stored blockhash replaces a recent one
+    #[arg(long, global = true, help_heading = "Offline signing")]
+    nonce: Option<Pubkey>,
+
+    /// Authority of the nonce account (default: the fee payer)
+    #[arg(long, global = true, requires = "nonce", help_heading = "Offline signing")]
+    nonce_authority: Option<Pubkey>,
+
+    /// Blockhash to use instead of fetching one (or the nonce) from the cluster
+    #[arg(long, global = true, help_heading = "Offline signing")]
+    blockhash: Option<Hash>,
+
+    /// Print bare instructions and a base58 message without fee payer,
+    /// blockhash or nonce, for a Squads multisig proposal
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["dry_run", "unsigned", "fee_payer", "nonce", "blockhash"],
+        help_heading = "Offline signing"
+    )]
+    squads: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Output {
+    Text,
+    Json,
+}
+
+/// Amounts are in whole tokens (`10.5`), durations in seconds or with a
+/// `s`/`m`/`h`/`d` suffix (`90d`).
+#[derive(Subcommand)]
+enum Command {
+    /// Print the decoded `SaleState` and vault balances
+    Status,
+    /// Print a wallet's vesting, grant or a position: vested, claimable and next unlock
+    Vesting {
+        /// Defaults to the keypair's wallet
+        #[arg(conflicts_with = "position")]
+        wallet: Option<Pubkey>,
+        /// Show the wallet's grant instead of its purchased vesting
+        #[arg(long, conflicts_with = "position")]
+        grant: bool,
+        /// Show the position vesting of this NFT mint
+        #[arg(long, value_name = "MINT")]
+        position: Option<Pubkey>,
+    },
+    /// Initialize the sale with vesting parameters and price (signer: authority)
+    InitializeSale {
+        #[arg(long, value_parser = parse_duration)]
+        cliff: i64,
+        #[arg(long, value_parser = parse_duration)]
+        vesting: i64,
+        /// USDC per VECT
+        #[arg(long, value_parser = parse_usdc)]
+        price: u64,
+    },
+    /// Fund the VECT vault with tokens for sale (signer: authority)
+    AdminFundVault {
+        #[arg(value_parser = parse_vect)]
+        amount: u64,
+        /// VECT token account to fund from (default: the authority's ATA)
+        #[arg(long)]
+        from: Option<Pubkey>,
+    },
+    /// Buy VECT with USDC into the wallet's vesting
+    BuyWithUsdc {
+        #[arg(value_parser = parse_usdc)]
+        amount: u64,
+        /// USDC token account to pay from (default: the buyer's ATA)
+        #[arg(long)]
+        from: Option<Pubkey>,
+    },
+    /// Buy VECT with USDC and receive the vesting as a position NFT
+    BuyPositionWithUsdc {
+        #[arg(value_parser = parse_usdc)]
+        amount: u64,
+        #[arg(long)]
+        from: Option<Pubkey>,
+    },
+    /// Claim the wallet's vested VECT
+    Claim,
+    /// Claim vested VECT of a position NFT the wallet holds
+    ClaimPosition {
+        position_mint: Pubkey,
+    },
+    /// Claim for many wallets into their VECT ATAs; anyone may crank this
+    BatchClaim {
+        #[arg(required = true, num_args = 1..=vectaiproj_client::vectaiproj::MAX_BATCH_CLAIMS)]
+        wallets: Vec<Pubkey>,
+    },
+    /// Move the wallet's unclaimed vesting to a new beneficiary
+    TransferVesting {
+        new_beneficiary: Pubkey,
+    },
+    /// Allocate VECT from the vault to a team/advisor grant (signer: authority)
+    CreateGrant {
+        beneficiary: Pubkey,
+        #[arg(value_parser = parse_vect)]
+        amount: u64,
+        #[arg(long, value_parser = parse_duration)]
+        cliff: i64,
+        #[arg(long, value_parser = parse_duration)]
+        vesting: i64,
+    },
+    /// Claim the wallet's vested grant tokens
+    ClaimGrant,
+    /// Revoke a grant; what has vested stays claimable (signer: authority)
+    RevokeGrant {
+        beneficiary: Pubkey,
+    },
+    /// Close the wallet's fully claimed vesting and reclaim its rent
+    CloseVesting,
+    /// Close a fully claimed position, burning its NFT, and reclaim its rent
+    ClosePosition {
+        position_mint: Pubkey,
+    },
+    /// Close the wallet's fully claimed grant and reclaim its rent
+    CloseGrant,
+    /// Close a settled sale, returning unsold VECT (signer: authority)
+    CloseSale {
+        /// VECT token account receiving unsold tokens (default: the authority's ATA)
+        #[arg(long)]
+        to: Option<Pubkey>,
+    },
+    /// Withdraw USDC from the treasury (signer: authority)
+    WithdrawUsdc {
+        #[arg(value_parser = parse_usdc)]
+        amount: u64,
+        /// USDC token account to withdraw to (default: the authority's ATA)
+        #[arg(long)]
+        to: Option<Pubkey>,
+    },
+    /// Pause purchases (signer: authority)
+    PauseSale,
+    /// Resume purchases (signer: authority)
+    UnpauseSale,
+    /// End the sale permanently (signer: authority)
+    EndSale,
+    /// Allow or forbid beneficiaries to transfer their vesting (signer: authority)
+    SetVestingTransfers {
+        #[arg(action = ArgAction::Set)]
+        enabled: bool,
+    },
+    /// Update the price (signer: authority)
+    UpdatePrice {
+        /// New USDC per VECT
+        #[arg(value_parser = parse_usdc)]
+        price: u64,
+    },
+    /// Add the keypair's signature to a serialized transaction; works offline
+    Sign {
+        /// Base64 or base58 transaction, a file containing one, or `-` for stdin
+        transaction: String,
+    },
+    /// Send a fully signed serialized transaction
+    Broadcast {
+        /// Base64 or base58 transaction, a file containing one, or `-` for stdin
+        transaction: String,
+    },
+}
+
+/// What every command needs: the cluster, the signer and the sale.
+struct Session {
+    rpc: Rpc,
+    config: Config,
+    /// `None` when no keypair is configured, e.g. when the authority is cold.
+    signer: Option<Keypair>,
+    authority: Pubkey,
+    sale: Pubkey,
+    dry_run: bool,
+    unsigned: bool,
+    fee_payer: Option<Pubkey>,
+    nonce: Option<Pubkey>,
+    nonce_authority: Option<Pubkey>,
+    blockhash: Option<Hash>,
+    squads: bool,
+    output: Output,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let output = cli.output;
+    if let Err(err) = run(cli) {
+        match output {
+            Output::Text => eprintln!("error: {err:#}"),
+            Output::Json => println!("{}", json!({ "error": format!("{err:#}") })),
+        }
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    if let Command::Sign { transaction } = &cli.command {
+        // Signing machines may be air-gapped: no RPC, and the config is only
+        // needed when `--keypair` is not given.
+        let keypair_path = match cli.keypair.as_deref() {
+            Some(path) => config::expand_home(path),
+            None => Config::load(&cli.config)?
+                .keypair_path()
+                .context("no keypair to sign with; pass --keypair")?,
+        };
+        return sign(&read_transaction(transaction)?, &read_keypair(&keypair_path)?, cli.output);
+    }
+
+    let config = Config::load(&cli.config)?;
+    let signer = cli
+        .keypair
+        .as_deref()
+        .map(config::expand_home)
+        .or_else(|| config.keypair_path())
+        .map(|path| read_keypair(&path))
+        .transpose()?;
+    let authority = match (config.sale.authority()?, &signer) {
+        (Some(authority), _) => authority,
+        (None, Some(signer)) => signer.pubkey(),
+        (None, None) => bail!("set `sale.authority` or a keypair in the config"),
+    };
+    let session = Session {
+        rpc: Rpc::new(cli.url.unwrap_or(config.rpc_url.clone()), config.commitment.clone()),
+        sale: pda::sale(&authority).0,
+        authority,
+        signer,
+        config,
+        dry_run: cli.dry_run,
+        unsigned: cli.unsigned,
+        fee_payer: cli.fee_payer,
+        nonce: cli.nonce,
+        nonce_authority: cli.nonce_authority,
+        blockhash: cli.blockhash,
+        squads: cli.squads,
+        output: cli.output,
+    };
+    session.execute(cli.command)
+}
+
+fn read_keypair(path: &Path) -> Result<Keypair> {
+    read_keypair_file(path).map_err(|e| anyhow!("reading keypair {}: {e}", path.display()))
+}
+
+/// A serialized transaction given inline, as a file, or as `-` for stdin.
+fn read_transaction(arg: &str) -> Result<Transaction> {
+    let text = if arg == "-" {
+        std::io::read_to_string(std::io::stdin()).context("reading stdin")?
+    } else if Path::new(arg).is_file() {
+        std::fs::read_to_string(arg).with_context(|| format!("reading {arg}"))?
+    } else {
+        arg.to_string()
+    };
+    offline::decode(&text)
+}
+
+/// Add `keypair`'s signature to `tx` without touching the network.
+fn sign(tx: &Transaction, keypair: &Keypair, output: Output) -> Result<()> {
+    let mut tx = tx.clone();
+    if !offline::signers(&tx).iter().any(|(key, _)| *key == keypair.pubkey()) {
+        bail!("{} is not a signer of this transaction", keypair.pubkey());
+    }
+    let blockhash = tx.message.recent_blockhash;
+    tx.try_partial_sign(&[keypair], blockhash)?;
+    print_transaction(&tx, output)
+}
+
+fn print_transaction(tx: &Transaction, output: Output) -> Result<()> {
+    match output {
+        Output::Json => println!("{}", offline::to_json(tx)?),
+        Output::Text => offline::print_text(tx)?,
+    }
+    Ok(())
+}
+
+impl Session {
+    fn execute(&self, command: Command) -> Result<()> {
+        let sale = self.sale;
+        let ix = match command {
+            Command::Status => return self.status(),
+            Command::Vesting { wallet, grant, position } => {
+                let allocation = match (position, grant) {
+                    (Some(mint), _) => Allocation::Position(mint),
+                    (None, grant) => {
+                        let wallet = match wallet {
+                            Some(wallet) => wallet,
+                            None => self.wallet()?,
+                        };
+                        if grant {
+                            Allocation::Grant(wallet)
+                        } else {
+                            Allocation::Vesting(wallet)
+                        }
+                    }
+                };
+                return self.vesting(allocation);
+            }
+            Command::Sign { .. } => unreachable!("signing is handled before connecting"),
+            Command::Broadcast { transaction } => {
+                return self.broadcast(&read_transaction(&transaction)?)
+            }
+            Command::InitializeSale { cliff, vesting, price } => instructions::initialize_sale(
+                self.admin()?,
+                self.config.sale.vect_mint()?,
+                self.config.sale.usdc_mint()?,
+                cliff,
+                vesting,
+                price,
+            ),
+            Command::AdminFundVault { amount, from } => {
+                let from = self.or_ata(from, self.authority, self.config.sale.vect_mint()?);
+                instructions::admin_fund_vault(self.admin()?, from, amount)
+            }
+            Command::BuyWithUsdc { amount, from } => {
+                let wallet = self.wallet()?;
+                let from = self.or_ata(from, wallet, self.config.sale.usdc_mint()?);
+                instructions::buy_with_usdc(sale, wallet, from, amount)
+            }
+            Command::BuyPositionWithUsdc { amount, from } => {
+                let wallet = self.wallet()?;
+                let from = self.or_ata(from, wallet, self.config.sale.usdc_mint()?);
+                let position_mint = Keypair::new();
+                let ix = instructions::buy_position_with_usdc(
+                    sale,
+                    wallet,
+                    position_mint.pubkey(),
+                    from,
+                    amount,
+                );
+                return self.send(vec![ix], &[&position_mint]);
+            }
+            Command::Claim => instructions::claim(sale, self.wallet()?, self.config.sale.vect_mint()?),
+            Command::ClaimPosition { position_mint } => instructions::claim_position(
+                sale,
+                self.wallet()?,
+                position_mint,
+                self.config.sale.vect_mint()?,
+            ),
+            Command::BatchClaim { wallets } => {
+                let vect_mint = self.config.sale.vect_mint()?;
+                let claims: Vec<_> = wallets
+                    .iter()
+                    .map(|w| (*w, get_associated_token_address(w, &vect_mint)))
+                    .collect();
+                instructions::batch_claim(sale, &claims)
+            }
+            Command::TransferVesting { new_beneficiary } => {
+                instructions::transfer_vesting(sale, self.wallet()?, new_beneficiary)
+            }
+            Command::CreateGrant { beneficiary, amount, cliff, vesting } => {
+                instructions::create_grant(self.admin()?, beneficiary, amount, cliff, vesting)
+            }
+            Command::ClaimGrant => instructions::claim_grant(sale, self.wallet()?, self.config.sale.vect_mint()?),
+            Command::RevokeGrant { beneficiary } => instructions::revoke_grant(self.admin()?, beneficiary),
+            Command::CloseVesting => instructions::close_vesting(sale, self.wallet()?),
+            Command::ClosePosition { position_mint } => {
+                instructions::close_position(sale, self.wallet()?, position_mint)
+            }
+            Command::CloseGrant => instructions::close_grant(sale, self.wallet()?),
+            Command::CloseSale { to } => {
+                let to = self.or_ata(to, self.authority, self.config.sale.vect_mint()?);
+                instructions::close_sale(self.admin()?, to)
+            }
+            Command::WithdrawUsdc { amount, to } => {
+                let to = self.or_ata(to, self.authority, self.config.sale.usdc_mint()?);
+                instructions::withdraw_usdc(self.admin()?, to, amount)
+            }
+            Command::PauseSale => instructions::pause_sale(self.admin()?),
+            Command::UnpauseSale => instructions::unpause_sale(self.admin()?),
+            Command::EndSale => instructions::end_sale(self.admin()?),
+            Command::SetVestingTransfers { enabled } => {
+                instructions::set_vesting_transfers(self.admin()?, enabled)
+            }
+            Command::UpdatePrice { price } => instructions::update_price(self.admin()?, price),
+        };
+        self.send(vec![ix], &[])
+    }
+
+    /// The keypair's wallet, which user instructions act for.
+    fn wallet(&self) -> Result<Pubkey> {
+        self.signer
+            .as_ref()
+            .map(|signer| signer.pubkey())
+            .context("no keypair configured; pass --keypair")
+    }
+
+    /// The sale authority, which admin instructions need as signer. With
+    /// `--unsigned` or `--squads` it signs later, so it need not be the
+    /// local keypair.
+    fn admin(&self) -> Result<Pubkey> {
+        if !self.unsigned && !self.squads && self.wallet().ok() != Some(self.authority) {
+            bail!(
+                "sale authority {} must sign, but the keypair is {}; \
+                 use --unsigned or --squads to build the transaction for offline signing",
+                self.authority,
+                self.wallet().map_or_else(|_| "not set".to_string(), |key| key.to_string())
+            );
+        }
+        Ok(self.authority)
+    }
+
+    fn or_ata(&self, account: Option<Pubkey>, owner: Pubkey, mint: Pubkey) -> Pubkey {
+        account.unwrap_or_else(|| get_associated_token_address(&owner, &mint))
+    }
+
+    /// Sign and send `ixs`, simulate them with `--dry-run`, or print them for
+    /// offline signing with `--unsigned` or a multisig with `--squads`. `extra_signers` are keypairs made
+    /// for this transaction (a position mint); they sign in every mode.
+    fn send(&self, ixs: Vec<Instruction>, extra_signers: &[&Keypair]) -> Result<()> {
+        if self.squads {
+            if !extra_signers.is_empty() {
+                bail!("a multisig cannot add the signature of a new keypair; use --unsigned");
+            }
+            match self.output {
+                Output::Json => println!("{}", offline::squads_json(&ixs)),
+                Output::Text => offline::print_squads_text(&ixs),
+            }
+            return Ok(());
+        }
+
+        let mut tx = self.transaction(ixs)?;
+
+        if self.dry_run {
+            let simulation = self.rpc.simulate(&tx)?;
+            match self.output {
+                Output::Json => println!(
+                    "{}",
+                    json!({
+                        "simulation": {
+                            "err": simulation.err,
+                            "logs": simulation.logs,
+                            "units_consumed": simulation.units_consumed,
+                        }
+                    })
+                ),
+                Output::Text => {
+                    for log in &simulation.logs {
+                        println!("  {log}");
+                    }
+                    if let Some(units) = simulation.units_consumed {
+                        println!("Compute units: {units}");
+                    }
+                    if simulation.err.is_none() {
+                        println!("Simulation succeeded");
+                    }
+                }
+            }
+            if let Some(err) = simulation.err {
+                bail!("simulation failed: {err}");
+            }
+            return Ok(());
+        }
+
+        let blockhash = tx.message.recent_blockhash;
+        if self.unsigned {
+            tx.try_partial_sign(extra_signers, blockhash)?;
+            return print_transaction(&tx, self.output);
+        }
+
+        let signer = self
+            .signer
+            .as_ref()
+            .context("no keypair configured; pass --keypair or use --unsigned")?;
+        let mut signers: Vec<&dyn Signer> = vec![signer];
+        signers.extend(extra_signers.iter().map(|k| *k as &dyn Signer));
+        tx.try_sign(&signers, blockhash)
+            .context("signing; use --unsigned to collect other signatures offline")?;
+        self.submit(&tx)
+    }
+
+    /// Build the transaction for `ixs`: fee payer, blockhash and, with
+    /// `--nonce`, a leading `AdvanceNonceAccount`.
+    fn transaction(&self, mut ixs: Vec<Instruction>) -> Result<Transaction> {
+        let fee_payer = match (self.fee_payer, &self.signer) {
+            (Some(fee_payer), _) => fee_payer,
+            (None, Some(signer)) if !self.unsigned => signer.pubkey(),
+            _ => ixs
+                .iter()
+                .flat_map(|ix| &ix.accounts)
+                .find(|meta| meta.is_signer)
+                .map(|meta| meta.pubkey)
+                .context("no signer to pay fees; pass --fee-payer")?,
+        };
+
+        let mut blockhash = self.blockhash;
+        if let Some(nonce) = self.nonce {
+            let nonce_authority = self.nonce_authority.unwrap_or(fee_payer);
+            ixs.insert(0, system_instruction::advance_nonce_account(&nonce, &nonce_authority));
+            if blockhash.is_none() {
+                let data = self
+                    .rpc
+                    .account_data(&nonce)?
+                    .with_context(|| format!("nonce account {nonce} not found"))?;
+                blockhash = Some(offline::nonce_blockhash(&data, &nonce_authority)?);
+            }
+        }
+        let blockhash = match blockhash {
+            Some(blockhash) => blockhash,
+            // Simulation replaces the blockhash anyway.
+            None if self.dry_run => Hash::default(),
+            None => self.rpc.latest_blockhash()?,
+        };
+
+        let mut tx = Transaction::new_with_payer(&ixs, Some(&fee_payer));
+        tx.message.recent_blockhash = blockhash;
+        Ok(tx)
+    }
+
+    /// Send a transaction signed elsewhere.
+    fn broadcast(&self, tx: &Transaction) -> Result<()> {
+        let missing: Vec<_> = offline::signers(tx)
+            .into_iter()
+            .filter(|(_, signed)| !signed)
+            .map(|(key, _)| key.to_string())
+            .collect();
+        if !missing.is_empty() {
+            bail!("transaction is missing signatures from {}", missing.join(", "));
+        }
+        tx.verify().context("transaction has an invalid signature")?;
+        self.submit(tx)
+    }
+
+    fn submit(&self, tx: &Transaction) -> Result<()> {
+        let signature = self.rpc.send_and_confirm(tx)?;
+        match self.output {
+            Output::Json => println!("{}", json!({ "signature": signature.to_string() })),
+            Output::Text => println!("Signature: {signature}"),
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> Result<()> {
+        let data = self
+            .rpc
+            .account_data(&self.sale)?
+            .with_context(|| format!("no sale at {} for authority {}", self.sale, self.authority))?;
+        let state = vectaiproj_client::sale_state(&data).context("decoding SaleState")?;
+        let vault = self.token_balance(&state.vect_vault)?;
+        let treasury = self.token_balance(&state.usdc_treasury)?;
+
+        match self.output {
+            Output::Json => {
+                let mut value = sale_json(&state);
+                value["address"] = json!(self.sale.to_string());
+                value["vect_vault_balance"] = json!(vault);
+                value["usdc_treasury_balance"] = json!(treasury);
+                println!("{value}");
+            }
+            Output::Text => {
+                let status = if state.is_ended {
+                    "ended"
+                } else if state.is_paused {
+                    "paused"
+                } else {
+                    "active"
+                };
+                println!("Sale:               {}", self.sale);
+                println!("Status:             {status}");
+                println!("Authority:          {}", state.authority);
+                println!("VECT mint:          {}", state.vect_mint);
+                println!("USDC mint:          {}", state.usdc_mint);
+                println!("Price:              {} per VECT", usdc(state.usdc_price_per_vect));
+                println!("Cliff:              {}", duration(state.cliff_duration));
+                println!("Vesting:            {}", duration(state.vesting_duration));
+                println!("VECT sold:          {}", vect(state.total_vect_sold));
+                println!("VECT granted:       {}", vect(state.total_vect_granted));
+                println!("VECT claimed:       {}", vect(state.total_vect_claimed));
+                println!("USDC raised:        {}", usdc(state.total_usdc_raised));
+                println!("VECT vault:         {} ({})", vect(vault), state.vect_vault);
+                println!("USDC treasury:      {} ({})", usdc(treasury), state.usdc_treasury);
+                println!("Vesting transfers:  {}", state.vesting_transfers_enabled);
+            }
+        }
+        Ok(())
+    }
+
+    fn vesting(&self, allocation: Allocation) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+        let address = allocation.address(&self.sale);
+        let data = self.rpc.account_data(&address)?;
+        let data = match (data, allocation) {
+            (Some(data), _) => data,
+            (None, Allocation::Vesting(wallet)) => bail!("{wallet} has no vesting in this sale"),
+            (None, Allocation::Grant(wallet)) => bail!("{wallet} has no grant in this sale"),
+            (None, Allocation::Position(mint)) => bail!("no position for mint {mint} in this sale"),
+        };
+
+        let mut value = json!({ "address": address.to_string() });
+        let (total, claimed, status, revoked) = match allocation {
+            Allocation::Grant(wallet) => {
+                let grant = vectaiproj_client::grant(&data)?;
+                let status = vectaiproj_client::grant_status(&grant, now)
+                    .context("vesting math overflowed")?;
+                value["beneficiary"] = json!(wallet.to_string());
+                value["start_time"] = json!(grant.start_time);
+                value["cliff_duration"] = json!(grant.cliff_duration);
+                value["vesting_duration"] = json!(grant.vesting_duration);
+                value["is_revoked"] = json!(grant.is_revoked);
+                (grant.total_vect_amount, grant.claimed_amount, status, grant.is_revoked)
+            }
+            Allocation::Vesting(_) | Allocation::Position(_) => {
+                let state = vectaiproj_client::sale_state(
+                    &self.rpc.account_data(&self.sale)?.context("sale not found")?,
+                )?;
+                let vesting = vectaiproj_client::vesting(&data)?;
+                let status = vectaiproj_client::vesting_status(&state, &vesting, now)
+                    .context("vesting math overflowed")?;
+                match allocation {
+                    Allocation::Position(mint) => value["position_mint"] = json!(mint.to_string()),
+                    _ => value["beneficiary"] = json!(vesting.beneficiary.to_string()),
+                }
+                value["start_time"] = json!(vesting.start_time);
+                (vesting.total_vect_amount, vesting.claimed_amount, status, false)
+            }
+        };
+
+        match self.output {
+            Output::Json => {
+                value["total_vect_amount"] = json!(total);
+                value["claimed_amount"] = json!(claimed);
+                value["vested"] = json!(status.vested);
+                value["claimable"] = json!(status.claimable);
+                value["next_unlock"] = json!(status.next_unlock);
+                println!("{value}");
+            }
+            Output::Text => {
+                let label = match allocation {
+                    Allocation::Vesting(_) => "Vesting:",
+                    Allocation::Grant(_) => "Grant:",
+                    Allocation::Position(_) => "Position:",
+                };
+                println!("{label:<11} {address}");
+                if revoked {
+                    println!("Revoked:    frozen at its vested amount");
+                }
+                println!("Total:      {}", vect(total));
+                println!("Claimed:    {}", vect(claimed));
+                println!("Vested:     {}", vect(status.vested));
+                println!("Claimable:  {}", vect(status.claimable));
+                match status.next_unlock {
+                    Some(time) => println!("Next unlock: in {}", duration(time - now)),
+                    None => println!("Fully vested"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn token_balance(&self, account: &Pubkey) -> Result<u64> {
+        let data = self.rpc.account_data(account)?.with_context(|| format!("{account} not found"))?;
+        Ok(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+    }
+}
+
+/// What `vect-sale vesting` reports on.
+#[derive(Clone, Copy)]
+enum Allocation {
+    Vesting(Pubkey),
+    Grant(Pubkey),
+    Position(Pubkey),
+}
+
+impl Allocation {
+    fn address(self, sale: &Pubkey) -> Pubkey {
+        match self {
+            Allocation::Vesting(wallet) => pda::vesting(sale, &wallet).0,
+            Allocation::Grant(wallet) => pda::grant(sale, &wallet).0,
+            Allocation::Position(mint) => pda::position(sale, &mint).0,
+        }
+    }
+}
+
+fn sale_json(state: &SaleState) -> Value {
+    json!({
+        "authority": state.authority.to_string(),
+        "vect_mint": state.vect_mint.to_string(),
+        "usdc_mint": state.usdc_mint.to_string(),
+        "vect_vault": state.vect_vault.to_string(),
+        "usdc_treasury": state.usdc_treasury.to_string(),
+        "cliff_duration": state.cliff_duration,
+        "vesting_duration": state.vesting_duration,
+        "usdc_price_per_vect": state.usdc_price_per_vect,
+        "total_vect_sold": state.total_vect_sold,
+        "total_usdc_raised": state.total_usdc_raised,
+        "total_vect_granted": state.total_vect_granted,
+        "total_vect_claimed": state.total_vect_claimed,
+        "is_paused": state.is_paused,
+        "is_ended": state.is_ended,
+        "vesting_transfers_enabled": state.vesting_transfers_enabled,
+    })
+}
+
+// ============================================================================
+// Units
+// ============================================================================
+
+/// Parse a whole-token amount such as `10` or `0.05` into base units.
+fn parse_tokens(value: &str, decimals: u32) -> Result<u64> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        bail!("empty amount");
+    }
+    // `u64::from_str` would also take a leading `+`
+    if !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        bail!("amount must be digits with an optional `.`");
+    }
+    if fraction.len() > decimals as usize {
+        bail!("at most {decimals} decimal places");
+    }
+    let whole: u64 = if whole.is_empty() { 0 } else { whole.parse()? };
+    let fraction: u64 = format!("{fraction:0<width$}", width = decimals as usize).parse()?;
+    whole
+        .checked_mul(10u64.pow(decimals))
+        .and_then(|w| w.checked_add(fraction))
+        .context("amount too large")
+}
+
+fn format_tokens(amount: u64, decimals: u32) -> String {
+    let unit = 10u64.pow(decimals);
+    let fraction = format!("{:0width$}", amount % unit, width = decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (amount / unit).to_string()
+    } else {
+        format!("{}.{fraction}", amount / unit)
+    }
+}
+
+fn parse_vect(value: &str) -> Result<u64> {
+    parse_tokens(value, VECT_DECIMALS)
+}
+
+fn parse_usdc(value: &str) -> Result<u64> {
+    parse_tokens(value, USDC_DECIMALS)
+}
+
+fn vect(amount: u64) -> String {
+    format!("{} VECT", format_tokens(amount, VECT_DECIMALS))
+}
+
+fn usdc(amount: u64) -> String {
+    format!("{} USDC", format_tokens(amount, USDC_DECIMALS))
+}
+
+/// Parse `3600`, `3600s`, `60m`, `12h` or `90d` into seconds.
+fn parse_duration(value: &str) -> Result<i64> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "s"),
+    };
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => bail!("unknown duration unit `{unit}` (use s, m, h or d)"),
+    };
+    number
+        .parse::<i64>()?
+        .checked_mul(scale)
+        .context("duration too large")
+}
+
+fn duration(seconds: i64) -> String {
+    const DAY: i64 = 24 * 60 * 60;
+    if seconds % DAY == 0 {
+        format!("{} days", seconds / DAY)
+    } else {
+        format!("{seconds} seconds")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_token_amounts() {
+        assert_eq!(parse_tokens("10", 6).unwrap(), 10_000_000);
+        assert_eq!(parse_tokens("0.05", 6).unwrap(), 50_000);
+        assert_eq!(parse_tokens(".5", 6).unwrap(), 500_000);
+        assert_eq!(parse_tokens("1.000001", 6).unwrap(), 1_000_001);
+        assert!(parse_tokens("1.0000001", 6).is_err());
+        assert!(parse_tokens("-1", 6).is_err());
+        assert!(parse_tokens("+1", 6).is_err());
+        assert!(parse_tokens("1.+5", 6).is_err());
+        assert!(parse_tokens("1.5e3", 6).is_err());
+        assert!(parse_tokens(" 1", 6).is_err());
+        assert!(parse_tokens("", 6).is_err());
+        assert!(parse_tokens("18446744073709551615", 6).is_err());
+
+        assert_eq!(format_tokens(10_000_000, 6), "10");
+        assert_eq!(format_tokens(50_000, 6), "0.05");
+        assert_eq!(format_tokens(1_000_001, 6), "1.000001");
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("3600").unwrap(), 3600);
+        assert_eq!(parse_duration("90d").unwrap(), 90 * 86_400);
+        assert_eq!(parse_duration("12h").unwrap(), 12 * 3600);
+        assert!(parse_duration("1w").is_err());
+        assert!(parse_duration("d").is_err());
+        assert_eq!(duration(90 * 86_400), "90 days");
+    }
+
+    #[test]
+    fn vesting_looks_up_grants_and_positions() {
+        use clap::Parser;
+        let (sale, wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(Allocation::Vesting(wallet).address(&sale), pda::vesting(&sale, &wallet).0);
+        assert_eq!(Allocation::Grant(wallet).address(&sale), pda::grant(&sale, &wallet).0);
+        assert_eq!(Allocation::Position(wallet).address(&sale), pda::position(&sale, &wallet).0);
+
+        let wallet = wallet.to_string();
+        let parse = |args: &[&str]| Cli::try_parse_from(["vect-sale", "vesting"].iter().chain(args));
+        assert!(matches!(
+            parse(&[&wallet, "--grant"]).unwrap().command,
+            Command::Vesting { grant: true, position: None, .. }
+        ));
+        assert!(matches!(
+            parse(&["--position", &wallet]).unwrap().command,
+            Command::Vesting { wallet: None, position: Some(_), .. }
+        ));
+        assert!(parse(&["--grant", "--position", &wallet]).is_err());
+        assert!(parse(&[&wallet, "--position", &wallet]).is_err());
+    }
+
+    /// A session that never reaches the cluster: the blockhash is given.
+    fn offline_session(signer: Option<Keypair>, unsigned: bool) -> Session {
+        let config: Config = toml::from_str("rpc_url = \"http://127.0.0.1:1\"\n[sale]\n").unwrap();
+        let authority = Pubkey::new_unique();
+        Session {
+            rpc: Rpc::new(config.rpc_url.clone(), config.commitment.clone()),
+            config,
+            signer,
+            authority,
+            sale: pda::sale(&authority).0,
+            dry_run: false,
+            unsigned,
+            fee_payer: None,
+            nonce: None,
+            nonce_authority: None,
+            blockhash: Some(Hash::new_unique()),
+            squads: false,
+            output: Output::Text,
+        }
+    }
+
+    #[test]
+    fn transaction_falls_back_to_the_first_signer_and_prepends_the_nonce() {
+        let (keypair, authority) = (Keypair::new(), Pubkey::new_unique());
+        let keypair_pubkey = keypair.pubkey();
+        let ix = instructions::pause_sale(authority);
+
+        // Signing locally, the keypair pays.
+        let session = offline_session(Some(keypair), false);
+        let tx = session.transaction(vec![ix.clone()]).unwrap();
+        assert_eq!(tx.message.account_keys[0], keypair_pubkey);
+        assert_eq!(tx.message.recent_blockhash, session.blockhash.unwrap());
+
+        // Unsigned, the first signer of the instructions pays, not the keypair.
+        let mut session = offline_session(Some(Keypair::new()), true);
+        let tx = session.transaction(vec![ix.clone()]).unwrap();
+        assert_eq!(offline::signers(&tx), [(authority, false)]);
+
+        // An explicit fee payer wins; the nonce is advanced first, by the
+        // fee payer unless a nonce authority is given.
+        let (fee_payer, nonce) = (Pubkey::new_unique(), Pubkey::new_unique());
+        session.fee_payer = Some(fee_payer);
+        session.nonce = Some(nonce);
+        let tx = session.transaction(vec![ix.clone()]).unwrap();
+        assert_eq!(tx.message.account_keys[0], fee_payer);
+        assert_eq!(
+            offline::describe(&tx),
+            [format!("advance_nonce_account {nonce}"), "pause_sale".to_string()]
+        );
+        let advance = &tx.message.instructions[0];
+        assert_eq!(tx.message.account_keys[usize::from(advance.accounts[2])], fee_payer);
+
+        let nonce_authority = Pubkey::new_unique();
+        session.nonce_authority = Some(nonce_authority);
+        let tx = session.transaction(vec![ix]).unwrap();
+        let advance = &tx.message.instructions[0];
+        assert_eq!(tx.message.account_keys[usize::from(advance.accounts[2])], nonce_authority);
+        assert_eq!(offline::signers(&tx).len(), 3);
+
+        // Nothing signs a crank, so unsigned it needs --fee-payer.
+        let session = offline_session(None, true);
+        let crank = instructions::batch_claim(session.sale, &[(authority, Pubkey::new_unique())]);
+        assert!(session.transaction(vec![crank]).is_err());
+    }
+
+    #[test]
+    fn cli_mirrors_the_program_instructions() {
+        use clap::CommandFactory;
+        let names: Vec<_> = Cli::command()
+            .get_subcommands()
+            .map(|c| c.get_name().replace('-', "_"))
+            .collect();
+        for ix in [
+            "initialize_sale",
+            "admin_fund_vault",
+            "buy_with_usdc",
+            "buy_position_with_usdc",
+            "claim",
+            "claim_position",
+            "batch_claim",
+            "transfer_vesting",
+            "create_grant",
+            "claim_grant",
+            "revoke_grant",
+            "close_vesting",
+            "close_position",
+            "close_grant",
+            "close_sale",
+            "withdraw_usdc",
+            "pause_sale",
+            "unpause_sale",
+            "end_sale",
+            "set_vesting_transfers",
+            "update_price",
+        ] {
+            assert!(names.iter().any(|n| n == ix), "missing subcommand for {ix}");
+        }
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,355 @@
+//! Transactions that leave this machine unsigned: built with `--unsigned`,
+//! signed elsewhere (`vect-sale sign`, a hardware wallet, or a multisig) and
+//! sent later with `vect-sale broadcast`.
+//!
+//! Transactions travel as base64 or base58 of the wire format, the same bytes
+//! `sendTransaction` takes. With `--nonce` the blockhash is a durable nonce,
+//! so the transaction stays valid until the nonce is advanced instead of
+//! expiring after ~150 slots.
+//!
+//! A multisig such as Squads builds its own transaction around the
+//! instructions, so `--squads` prints only those: bare instructions and a
+//! base58 message with no fee payer, blockhash or nonce.
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use anyhow::{bail, Context, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde_json::{json, Value};
+use solana_nonce::state::State;
+use solana_nonce::versions::Versions;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use solana_system_interface::instruction::SystemInstruction;
+use vectaiproj_client::vectaiproj::instruction as ix;
+
+use crate::{duration, usdc, vect};
+
+pub fn encode_base64(tx: &Transaction) -> Result<String> {
+    Ok(BASE64_STANDARD.encode(bincode::serialize(tx).context("serializing transaction")?))
+}
+
+pub fn encode_base58(tx: &Transaction) -> Result<String> {
+    Ok(bs58::encode(bincode::serialize(tx).context("serializing transaction")?).into_string())
+}
+
+/// Parse a serialized transaction in base64 or base58.
+pub fn decode(text: &str) -> Result<Transaction> {
+    let text = text.trim();
+    let bytes = [BASE64_STANDARD.decode(text).ok(), bs58::decode(text).into_vec().ok()];
+    for bytes in bytes.into_iter().flatten() {
+        if let Ok(tx) = bincode::deserialize::<Transaction>(&bytes) {
+            if tx.signatures.len() == usize::from(tx.message.header.num_required_signatures) {
+                return Ok(tx);
+            }
+        }
+    }
+    bail!("not a base64 or base58 serialized transaction")
+}
+
+/// The blockhash stored in a durable nonce account, checking that
+/// `authority` may advance it.
+pub fn nonce_blockhash(data: &[u8], authority: &Pubkey) -> Result<Hash> {
+    let versions: Versions = bincode::deserialize(data).context("not a nonce account")?;
+    match versions.state() {
+        State::Initialized(nonce) if nonce.authority == *authority => Ok(nonce.blockhash()),
+        State::Initialized(nonce) => {
+            bail!("nonce authority is {}, not {authority}", nonce.authority)
+        }
+        State::Uninitialized => bail!("nonce account is not initialized"),
+    }
+}
+
+/// Required signers and whether each has signed, fee payer first.
+pub fn signers(tx: &Transaction) -> Vec<(Pubkey, bool)> {
+    tx.message
+        .account_keys
+        .iter()
+        .zip(&tx.signatures)
+        .map(|(key, signature)| (*key, *signature != Signature::default()))
+        .collect()
+}
+
+/// One line per instruction, with vesting_sale arguments decoded, so a
+/// signer can check what they are approving.
+pub fn describe(tx: &Transaction) -> Vec<String> {
+    let keys = &tx.message.account_keys;
+    tx.message
+        .instructions
+        .iter()
+        .map(|compiled| {
+            let program = keys[usize::from(compiled.program_id_index)];
+            let account = |i: usize| {
+                compiled
+                    .accounts
+                    .get(i)
+                    .map_or_else(|| "?".to_string(), |&k| keys[usize::from(k)].to_string())
+            };
+            if program == vectaiproj_client::ID {
+                sale_instruction(&compiled.data, account)
+                    .unwrap_or_else(|| "vesting_sale: unknown instruction".to_string())
+            } else if program == solana_system_interface::program::ID
+                && matches!(
+                    bincode::deserialize(&compiled.data),
+                    Ok(SystemInstruction::AdvanceNonceAccount)
+                )
+            {
+                format!("advance_nonce_account {}", account(0))
+            } else {
+                format!("{program}: {} bytes of data", compiled.data.len())
+            }
+        })
+        .collect()
+}
+
+fn sale_instruction(data: &[u8], account: impl Fn(usize) -> String) -> Option<String> {
+    let (discriminator, args) = (data.get(..8)?, data.get(8..)?);
+    macro_rules! decode {
+        ($ty:ty) => {
+            <$ty>::try_from_slice(args).ok()?
+        };
+    }
+    let named = [
+        (ix::Claim::DISCRIMINATOR, "claim"),
+        (ix::ClaimPosition::DISCRIMINATOR, "claim_position"),
+        (ix::BatchClaim::DISCRIMINATOR, "batch_claim"),
+        (ix::TransferVesting::DISCRIMINATOR, "transfer_vesting"),
+        (ix::ClaimGrant::DISCRIMINATOR, "claim_grant"),
+        (ix::CloseVesting::DISCRIMINATOR, "close_vesting"),
+        (ix::ClosePosition::DISCRIMINATOR, "close_position"),
+        (ix::CloseGrant::DISCRIMINATOR, "close_grant"),
+        (ix::PauseSale::DISCRIMINATOR, "pause_sale"),
+        (ix::UnpauseSale::DISCRIMINATOR, "unpause_sale"),
+        (ix::EndSale::DISCRIMINATOR, "end_sale"),
+    ];
+    if let Some((_, name)) = named.iter().find(|(d, _)| *d == discriminator) {
+        return Some(name.to_string());
+    }
+
+    Some(match discriminator {
+        d if d == ix::InitializeSale::DISCRIMINATOR => {
+            let a = decode!(ix::InitializeSale);
+            format!(
+                "initialize_sale cliff {}, vesting {}, price {} per VECT",
+                duration(a.cliff_duration),
+                duration(a.vesting_duration),
+                usdc(a.usdc_price_per_vect)
+            )
+        }
+        d if d == ix::AdminFundVault::DISCRIMINATOR => {
+            format!("admin_fund_vault {}", vect(decode!(ix::AdminFundVault).amount))
+        }
+        d if d == ix::BuyWithUsdc::DISCRIMINATOR => {
+            format!("buy_with_usdc {}", usdc(decode!(ix::BuyWithUsdc).usdc_amount))
+        }
+        d if d == ix::BuyPositionWithUsdc::DISCRIMINATOR => format!(
+            "buy_position_with_usdc {}",
+            usdc(decode!(ix::BuyPositionWithUsdc).usdc_amount)
+        ),
+        d if d == ix::CreateGrant::DISCRIMINATOR => {
+            let a = decode!(ix::CreateGrant);
+            format!(
+                "create_grant {} to {}, cliff {}, vesting {}",
+                vect(a.total_vect_amount),
+                account(2),
+                duration(a.cliff_duration),
+                duration(a.vesting_duration)
+            )
+        }
+        d if d == ix::RevokeGrant::DISCRIMINATOR => format!("revoke_grant {}", account(1)),
+        d if d == ix::CloseSale::DISCRIMINATOR => {
+            format!("close_sale, unsold VECT to {}", account(2))
+        }
+        d if d == ix::WithdrawUsdc::DISCRIMINATOR => format!(
+            "withdraw_usdc {} to {}",
+            usdc(decode!(ix::WithdrawUsdc).amount),
+            account(2)
+        ),
+        d if d == ix::SetVestingTransfers::DISCRIMINATOR => {
+            format!("set_vesting_transfers {}", decode!(ix::SetVestingTransfers).enabled)
+        }
+        d if d == ix::UpdatePrice::DISCRIMINATOR => format!(
+            "update_price {} per VECT",
+            usdc(decode!(ix::UpdatePrice).new_usdc_price_per_vect)
+        ),
+        _ => return None,
+    })
+}
+
+/// `--output json` form of a transaction being passed around for signatures.
+pub fn to_json(tx: &Transaction) -> Result<Value> {
+    Ok(json!({
+        "transaction": encode_base64(tx)?,
+        "transaction_base58": encode_base58(tx)?,
+        "blockhash": tx.message.recent_blockhash.to_string(),
+        "instructions": describe(tx),
+        "signers": signers(tx)
+            .iter()
+            .map(|(key, signed)| json!({ "pubkey": key.to_string(), "signed": signed }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+/// Message for `ixs` without a fee payer, blockhash or nonce, in base58. The
+/// first signer (the multisig vault) leads the account keys.
+pub fn encode_message_base58(ixs: &[Instruction]) -> String {
+    bs58::encode(Message::new(ixs, None).serialize()).into_string()
+}
+
+/// One line per instruction, decoded as for a transaction.
+fn describe_instructions(ixs: &[Instruction]) -> Vec<String> {
+    describe(&Transaction::new_unsigned(Message::new(ixs, None)))
+}
+
+fn instruction_json(ix: &Instruction) -> Value {
+    json!({
+        "program_id": ix.program_id.to_string(),
+        "accounts": ix
+            .accounts
+            .iter()
+            .map(|meta| json!({
+                "pubkey": meta.pubkey.to_string(),
+                "is_signer": meta.is_signer,
+                "is_writable": meta.is_writable,
+            }))
+            .collect::<Vec<_>>(),
+        "data": bs58::encode(&ix.data).into_string(),
+    })
+}
+
+/// `--squads --output json`: what a multisig proposal needs.
+pub fn squads_json(ixs: &[Instruction]) -> Value {
+    json!({
+        "message": encode_message_base58(ixs),
+        "summary": describe_instructions(ixs),
+        "instructions": ixs.iter().map(instruction_json).collect::<Vec<_>>(),
+    })
+}
+
+pub fn print_squads_text(ixs: &[Instruction]) {
+    println!("Instructions:");
+    for (ix, line) in ixs.iter().zip(describe_instructions(ixs)) {
+        println!("  {line}");
+        println!("    program {}", ix.program_id);
+        for meta in &ix.accounts {
+            let flags = match (meta.is_signer, meta.is_writable) {
+                (true, true) => "signer, writable",
+                (true, false) => "signer",
+                (false, true) => "writable",
+                (false, false) => "readonly",
+            };
+            println!("    {} ({flags})", meta.pubkey);
+        }
+        println!("    data (base58) {}", bs58::encode(&ix.data).into_string());
+    }
+    println!("Message (base58, no fee payer, blockhash or nonce):");
+    println!("{}", encode_message_base58(ixs));
+}
+
+pub fn print_text(tx: &Transaction) -> Result<()> {
+    println!("Blockhash: {}", tx.message.recent_blockhash);
+    println!("Instructions:");
+    for line in describe(tx) {
+        println!("  {line}");
+    }
+    println!("Signers:");
+    for (key, signed) in signers(tx) {
+        println!("  {key} {}", if signed { "signed" } else { "missing" });
+    }
+    println!("Transaction (base64):");
+    println!("{}", encode_base64(tx)?);
+    println!("Transaction (base58):");
+    println!("{}", encode_base58(tx)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_nonce::state::{Data, DurableNonce};
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_system_interface::instruction::advance_nonce_account;
+    use vectaiproj_client::instructions;
+
+    fn update_price_with_nonce(authority: Pubkey, nonce: Pubkey, blockhash: Hash) -> Transaction {
+        let ixs = [
+            advance_nonce_account(&nonce, &authority),
+            instructions::update_price(authority, 250_000),
+        ];
+        let mut tx = Transaction::new_with_payer(&ixs, Some(&authority));
+        tx.message.recent_blockhash = blockhash;
+        tx
+    }
+
+    #[test]
+    fn unsigned_transactions_round_trip() {
+        let (authority, nonce) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let tx = update_price_with_nonce(authority, nonce, Hash::new_unique());
+
+        assert_eq!(decode(&encode_base64(&tx).unwrap()).unwrap(), tx);
+        assert_eq!(decode(&format!("{}\n", encode_base58(&tx).unwrap())).unwrap(), tx);
+        assert!(decode("not a transaction").is_err());
+
+        assert_eq!(signers(&tx), [(authority, false)]);
+        assert_eq!(
+            describe(&tx),
+            [format!("advance_nonce_account {nonce}"), "update_price 0.25 USDC per VECT".to_string()]
+        );
+    }
+
+    #[test]
+    fn signatures_are_collected_one_at_a_time() {
+        let (authority, fee_payer) = (Keypair::new(), Keypair::new());
+        let ix = instructions::withdraw_usdc(authority.pubkey(), Pubkey::new_unique(), 1_500_000);
+        let mut tx = Transaction::new_with_payer(&[ix], Some(&fee_payer.pubkey()));
+        tx.message.recent_blockhash = Hash::new_unique();
+        assert!(describe(&tx)[0].starts_with("withdraw_usdc 1.5 USDC to "));
+
+        let blockhash = tx.message.recent_blockhash;
+        tx.try_partial_sign(&[&authority], blockhash).unwrap();
+        let mut tx = decode(&encode_base64(&tx).unwrap()).unwrap();
+        assert_eq!(signers(&tx), [(fee_payer.pubkey(), false), (authority.pubkey(), true)]);
+        assert!(tx.verify().is_err());
+
+        tx.try_partial_sign(&[&fee_payer], blockhash).unwrap();
+        assert!(tx.is_signed());
+        assert!(tx.verify().is_ok());
+    }
+
+    #[test]
+    fn squads_export_has_no_fee_payer_or_nonce() {
+        let authority = Pubkey::new_unique();
+        let ixs = [instructions::update_price(authority, 250_000)];
+        let message: Message =
+            bincode::deserialize(&bs58::decode(encode_message_base58(&ixs)).into_vec().unwrap())
+                .unwrap();
+
+        // The multisig vault is the only signer and leads the keys; the
+        // multisig supplies its own blockhash when it executes.
+        assert_eq!(message.header.num_required_signatures, 1);
+        assert_eq!(message.account_keys[0], authority);
+        assert_eq!(message.recent_blockhash, Hash::default());
+        assert_eq!(message.instructions.len(), 1);
+
+        let value = squads_json(&ixs);
+        assert_eq!(value["summary"][0], "update_price 0.25 USDC per VECT");
+        assert_eq!(value["instructions"][0]["program_id"], vectaiproj_client::ID.to_string());
+        assert_eq!(value["instructions"][0]["accounts"][1]["is_signer"], true);
+    }
+
+    #[test]
+    fn reads_the_durable_nonce() {
+        let (authority, hash) = (Pubkey::new_unique(), Hash::new_unique());
+        let durable_nonce = DurableNonce::from_blockhash(&hash);
+        let state = State::Initialized(Data::new(authority, durable_nonce, 5_000));
+        let data = bincode::serialize(&Versions::new(state)).unwrap();
+
+        assert_eq!(nonce_blockhash(&data, &authority).unwrap(), *durable_nonce.as_hash());
+        assert!(nonce_blockhash(&data, &Pubkey::new_unique()).is_err());
+        let uninitialized = bincode::serialize(&Versions::new(State::Uninitialized)).unwrap();
+        assert!(nonce_blockhash(&uninitialized, &authority).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! The handful of JSON-RPC calls the CLI needs, over blocking HTTP.
+
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use serde_json::{json, Value};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+
+use crate::offline::encode_base64;
+
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct Rpc {
+    url: String,
+    commitment: String,
+}
+
+/// Result of `simulateTransaction`.
+pub struct Simulation {
+    pub err: Option<Value>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+}
+
+impl Rpc {
+    pub fn new(url: String, commitment: String) -> Self {
+        Self { url, commitment }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let body = ureq::post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&request.to_string())
+            .with_context(|| format!("{method} request to {}", self.url))?
+            .into_string()?;
+        let mut response: Value = serde_json::from_str(&body)
+            .with_context(|| format!("{method} returned invalid JSON"))?;
+        if let Some(error) = response.get("error") {
+            bail!("{method} failed: {error}");
+        }
+        Ok(response["result"].take())
+    }
+
+    /// Raw data of `address`, or `None` if the account does not exist.
+    pub fn account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([address.to_string(), { "encoding": "base64", "commitment": self.commitment }]),
+        )?;
+        if result["value"].is_null() {
+            return Ok(None);
+        }
+        let data = result["value"]["data"][0]
+            .as_str()
+            .ok_or_else(|| anyhow!("getAccountInfo: unexpected response {result}"))?;
+        Ok(Some(BASE64_STANDARD.decode(data)?))
+    }
+
+    pub fn latest_blockhash(&self) -> Result<Hash> {
+        let result = self.call("getLatestBlockhash", json!([{ "commitment": self.commitment }]))?;
+        let hash = result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("getLatestBlockhash: unexpected response {result}"))?;
+        Ok(Hash::from_str(hash)?)
+    }
+
+    /// Simulate `tx` without checking signatures, against the latest blockhash.
+    pub fn simulate(&self, tx: &Transaction) -> Result<Simulation> {
+        let result = self.call(
+            "simulateTransaction",
+            json!([encode_base64(tx)?, {
+                "encoding": "base64",
+                "sigVerify": false,
+                "replaceRecentBlockhash": true,
+                "commitment": self.commitment,
+            }]),
+        )?;
+        let value = &result["value"];
+        Ok(Simulation {
+            err: Some(value["err"].clone()).filter(|err| !err.is_null()),
+            logs: value["logs"]
+                .as_array()
+                .map(|logs| logs.iter().filter_map(|l| l.as_str().map(String::from)).collect())
+                .unwrap_or_default(),
+            units_consumed: value["unitsConsumed"].as_u64(),
+        })
+    }
+
+    /// Send a signed transaction and wait until it reaches the configured commitment.
+    pub fn send_and_confirm(&self, tx: &Transaction) -> Result<Signature> {
+        let result = self.call(
+            "sendTransaction",
+            json!([encode_base64(tx)?, { "encoding": "base64", "preflightCommitment": self.commitment }]),
+        )?;
+        let signature = Signature::from_str(
+            result.as_str().ok_or_else(|| anyhow!("sendTransaction: unexpected response {result}"))?,
+        )?;
+
+        let started = Instant::now();
+        while started.elapsed() < CONFIRM_TIMEOUT {
+            let result = self.call("getSignatureStatuses", json!([[signature.to_string()]]))?;
+            let status = &result["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    bail!("transaction {signature} failed: {}", status["err"]);
+                }
+                let reached = match status["confirmationStatus"].as_str() {
+                    Some("finalized") => true,
+                    Some("confirmed") => self.commitment != "finalized",
+                    Some("processed") => self.commitment == "processed",
+                    _ => false,
+                };
+                if reached {
+                    return Ok(signature);
+                }
+            }
+            sleep(Duration::from_millis(500));
+        }
+        bail!("transaction {signature} was not confirmed within {CONFIRM_TIMEOUT:?}")
+    }
+}
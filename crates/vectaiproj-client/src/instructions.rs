@@ -0,0 +1,368 @@
+//! One builder per vesting_sale instruction.
+//!
+//! Builders take the wallets, mints and user token accounts a caller already
+//! knows and derive every PDA and associated token account the program
+//! expects. Signers are noted on each builder; they still have to sign the
+//! transaction.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token;
+use vectaiproj::{accounts, instruction};
+
+use crate::pda;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: vectaiproj::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+// ============================================================================
+// Sale setup (signer: sale authority)
+// ============================================================================
+
+pub fn initialize_sale(
+    authority: Pubkey,
+    vect_mint: Pubkey,
+    usdc_mint: Pubkey,
+    cliff_duration: i64,
+    vesting_duration: i64,
+    usdc_price_per_vect: u64,
+) -> Instruction {
+    let sale = pda::sale(&authority).0;
+    build(
+        accounts::InitializeSale {
+            sale_state: sale,
+            authority,
+            vect_mint,
+            usdc_mint,
+            vect_vault: pda::vect_vault(&sale).0,
+            usdc_treasury: pda::usdc_treasury(&sale).0,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::InitializeSale {
+            cliff_duration,
+            vesting_duration,
+            usdc_price_per_vect,
+        },
+    )
+}
+
+pub fn admin_fund_vault(authority: Pubkey, admin_vect_account: Pubkey, amount: u64) -> Instruction {
+    let sale = pda::sale(&authority).0;
+    build(
+        accounts::AdminFundVault {
+            sale_state: sale,
+            authority,
+            admin_vect_account,
+            vect_vault: pda::vect_vault(&sale).0,
+            token_program: token::ID,
+        },
+        instruction::AdminFundVault { amount },
+    )
+}
+
+// ============================================================================
+// Purchases (signer: buyer; `buy_position_with_usdc` also the new mint)
+// ============================================================================
+
+pub fn buy_with_usdc(
+    sale: Pubkey,
+    buyer: Pubkey,
+    buyer_usdc_account: Pubkey,
+    usdc_amount: u64,
+) -> Instruction {
+    build(
+        accounts::BuyWithUsdc {
+            sale_state: sale,
+            vesting: pda::vesting(&sale, &buyer).0,
+            buyer,
+            buyer_usdc_account,
+            usdc_treasury: pda::usdc_treasury(&sale).0,
+            vect_vault: pda::vect_vault(&sale).0,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::BuyWithUsdc { usdc_amount },
+    )
+}
+
+/// `position_mint` is a fresh keypair; the program creates the mint and the
+/// buyer's associated token account for it.
+pub fn buy_position_with_usdc(
+    sale: Pubkey,
+    buyer: Pubkey,
+    position_mint: Pubkey,
+    buyer_usdc_account: Pubkey,
+    usdc_amount: u64,
+) -> Instruction {
+    build(
+        accounts::BuyPositionWithUsdc {
+            sale_state: sale,
+            position_mint,
+            position_vesting: pda::position(&sale, &position_mint).0,
+            buyer,
+            buyer_usdc_account,
+            buyer_position_account: get_associated_token_address(&buyer, &position_mint),
+            usdc_treasury: pda::usdc_treasury(&sale).0,
+            vect_vault: pda::vect_vault(&sale).0,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::BuyPositionWithUsdc { usdc_amount },
+    )
+}
+
+// ============================================================================
+// Claims (signer: beneficiary or position holder)
+// ============================================================================
+
+/// Pays into the beneficiary's VECT associated token account, created if needed.
+pub fn claim(sale: Pubkey, beneficiary: Pubkey, vect_mint: Pubkey) -> Instruction {
+    build(
+        accounts::Claim {
+            sale_state: sale,
+            vesting: pda::vesting(&sale, &beneficiary).0,
+            beneficiary,
+            vect_mint,
+            beneficiary_vect_account: get_associated_token_address(&beneficiary, &vect_mint),
+            vect_vault: pda::vect_vault(&sale).0,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::Claim {},
+    )
+}
+
+pub fn claim_position(
+    sale: Pubkey,
+    holder: Pubkey,
+    position_mint: Pubkey,
+    vect_mint: Pubkey,
+) -> Instruction {
+    build(
+        accounts::ClaimPosition {
+            sale_state: sale,
+            position_vesting: pda::position(&sale, &position_mint).0,
+            position_mint,
+            holder,
+            holder_position_account: get_associated_token_address(&holder, &position_mint),
+            vect_mint,
+            holder_vect_account: get_associated_token_address(&holder, &vect_mint),
+            vect_vault: pda::vect_vault(&sale).0,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimPosition {},
+    )
+}
+
+/// Crank claims for many wallets; no signer besides the fee payer.
+///
+/// `claims` are `(beneficiary, beneficiary's VECT token account)` pairs, at
+/// most `MAX_BATCH_CLAIMS` of them.
+pub fn batch_claim(sale: Pubkey, claims: &[(Pubkey, Pubkey)]) -> Instruction {
+    let mut ix = build(
+        accounts::BatchClaim {
+            sale_state: sale,
+            vect_vault: pda::vect_vault(&sale).0,
+            token_program: token::ID,
+        },
+        instruction::BatchClaim {},
+    );
+    for (beneficiary, destination) in claims {
+        ix.accounts
+            .push(AccountMeta::new(pda::vesting(&sale, beneficiary).0, false));
+        ix.accounts.push(AccountMeta::new(*destination, false));
+    }
+    ix
+}
+
+pub fn transfer_vesting(sale: Pubkey, beneficiary: Pubkey, new_beneficiary: Pubkey) -> Instruction {
+    build(
+        accounts::TransferVesting {
+            sale_state: sale,
+            vesting: pda::vesting(&sale, &beneficiary).0,
+            new_vesting: pda::vesting(&sale, &new_beneficiary).0,
+            beneficiary,
+            new_beneficiary,
+            system_program: system_program::ID,
+        },
+        instruction::TransferVesting {},
+    )
+}
+
+pub fn close_vesting(sale: Pubkey, beneficiary: Pubkey) -> Instruction {
+    build(
+        accounts::CloseVesting {
+            vesting: pda::vesting(&sale, &beneficiary).0,
+            beneficiary,
+        },
+        instruction::CloseVesting {},
+    )
+}
+
+/// Burns the position NFT held in the holder's associated token account.
+pub fn close_position(sale: Pubkey, holder: Pubkey, position_mint: Pubkey) -> Instruction {
+    build(
+        accounts::ClosePosition {
+            position_vesting: pda::position(&sale, &position_mint).0,
+            position_mint,
+            holder,
+            holder_position_account: get_associated_token_address(&holder, &position_mint),
+            token_program: token::ID,
+        },
+        instruction::ClosePosition {},
+    )
+}
+
+// ============================================================================
+// Grants
+// ============================================================================
+
+/// Signer: sale authority.
+pub fn create_grant(
+    authority: Pubkey,
+    beneficiary: Pubkey,
+    total_vect_amount: u64,
+    cliff_duration: i64,
+    vesting_duration: i64,
+) -> Instruction {
+    let sale = pda::sale(&authority).0;
+    build(
+        accounts::CreateGrant {
+            sale_state: sale,
+            grant: pda::grant(&sale, &beneficiary).0,
+            beneficiary,
+            authority,
+            vect_vault: pda::vect_vault(&sale).0,
+            system_program: system_program::ID,
+        },
+        instruction::CreateGrant {
+            total_vect_amount,
+            cliff_duration,
+            vesting_duration,
+        },
+    )
+}
+
+/// Signer: beneficiary. Pays into their VECT associated token account.
+pub fn claim_grant(sale: Pubkey, beneficiary: Pubkey, vect_mint: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimGrant {
+            sale_state: sale,
+            grant: pda::grant(&sale, &beneficiary).0,
+            beneficiary,
+            vect_mint,
+            beneficiary_vect_account: get_associated_token_address(&beneficiary, &vect_mint),
+            vect_vault: pda::vect_vault(&sale).0,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimGrant {},
+    )
+}
+
+/// Signer: sale authority.
+pub fn revoke_grant(authority: Pubkey, beneficiary: Pubkey) -> Instruction {
+    let sale = pda::sale(&authority).0;
+    build(
+        accounts::RevokeGrant {
+            sale_state: sale,
+            grant: pda::grant(&sale, &beneficiary).0,
+            authority,
+        },
+        instruction::RevokeGrant {},
+    )
+}
+
+/// Signer: beneficiary.
+pub fn close_grant(sale: Pubkey, beneficiary: Pubkey) -> Instruction {
+    build(
+        accounts::CloseGrant {
+            grant: pda::grant(&sale, &beneficiary).0,
+            beneficiary,
+        },
+        instruction::CloseGrant {},
+    )
+}
+
+// ============================================================================
+// Admin (signer: sale authority)
+// ============================================================================
+
+pub fn close_sale(authority: Pubkey, authority_vect_account: Pubkey) -> Instruction {
+    let sale = pda::sale(&authority).0;
+    build(
+        accounts::CloseSale {
+            sale_state: sale,
+            authority,
+            authority_vect_account,
+            vect_vault: pda::vect_vault(&sale).0,
+            usdc_treasury: pda::usdc_treasury(&sale).0,
+            token_program: token::ID,
+        },
+        instruction::CloseSale {},
+    )
+}
+
+pub fn withdraw_usdc(authority: Pubkey, authority_usdc_account: Pubkey, amount: u64) -> Instruction {
+    let sale = pda::sale(&authority).0;
+    build(
+        accounts::WithdrawUsdc {
+            sale_state: sale,
+            authority,
+            authority_usdc_account,
+            usdc_treasury: pda::usdc_treasury(&sale).0,
+            token_program: token::ID,
+        },
+        instruction::WithdrawUsdc { amount },
+    )
+}
+
+fn update_sale_state(authority: Pubkey) -> accounts::UpdateSaleState {
+    accounts::UpdateSaleState {
+        sale_state: pda::sale(&authority).0,
+        authority,
+    }
+}
+
+pub fn pause_sale(authority: Pubkey) -> Instruction {
+    build(update_sale_state(authority), instruction::PauseSale {})
+}
+
+pub fn unpause_sale(authority: Pubkey) -> Instruction {
+    build(update_sale_state(authority), instruction::UnpauseSale {})
+}
+
+pub fn end_sale(authority: Pubkey) -> Instruction {
+    build(update_sale_state(authority), instruction::EndSale {})
+}
+
+pub fn set_vesting_transfers(authority: Pubkey, enabled: bool) -> Instruction {
+    build(
+        update_sale_state(authority),
+        instruction::SetVestingTransfers { enabled },
+    )
+}
+
+pub fn update_price(authority: Pubkey, new_usdc_price_per_vect: u64) -> Instruction {
+    build(
+        update_sale_state(authority),
+        instruction::UpdatePrice {
+            new_usdc_price_per_vect,
+        },
+    )
+}
@@ -0,0 +1,143 @@
+//! Rust client for the vesting_sale program (`programs/vectaiproj`).
+//!
+//! - [`pda`]: addresses of the sale, its vaults, vestings and grants
+//! - [`instructions`]: a builder for every instruction
+//! - [`sale_state`], [`vesting`], [`grant`]: decode fetched account data
+//! - [`vesting_status`]: vested, claimable and next unlock, as `claim` sees them
+//! - [`grant_status`]: the same for grants, as `claim_grant` sees them
+//!
+//! Account and argument types come straight from the program crate (built
+//! with `no-entrypoint`), so the client cannot drift from the on-chain layout.
+
+use anchor_lang::AccountDeserialize;
+
+pub mod instructions;
+pub mod pda;
+
+pub use vectaiproj::{self, Grant, SaleState, Vesting, ID};
+pub use vectaiproj_vesting::{GrantSchedule, Schedule, Status, Unlock};
+
+/// Decode `SaleState` account data, checking its discriminator.
+pub fn sale_state(data: &[u8]) -> anchor_lang::Result<SaleState> {
+    SaleState::try_deserialize(&mut &data[..])
+}
+
+/// Decode `Vesting` account data, checking its discriminator.
+pub fn vesting(data: &[u8]) -> anchor_lang::Result<Vesting> {
+    Vesting::try_deserialize(&mut &data[..])
+}
+
+/// Decode `Grant` account data, checking its discriminator.
+pub fn grant(data: &[u8]) -> anchor_lang::Result<Grant> {
+    Grant::try_deserialize(&mut &data[..])
+}
+
+/// Vested, claimable and next unlock time of `vesting` at `now`, using the
+/// same math as the program. Use `vesting.schedule(sale).timeline(..)` for
+/// the full unlock timeline.
+pub fn vesting_status(sale: &SaleState, vesting: &Vesting, now: i64) -> Option<Status> {
+    vesting.schedule(sale).status(vesting.claimed_amount, now)
+}
+
+/// Vested, claimable and next unlock time of `grant` at `now`, as
+/// `claim_grant` sees them; revoked grants stay at their reduced total.
+pub fn grant_status(grant: &Grant, now: i64) -> Option<Status> {
+    grant.schedule().status(grant.claimed_amount, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::{AccountSerialize, Discriminator};
+    use anchor_spl::associated_token::get_associated_token_address;
+
+    #[test]
+    fn derives_sale_addresses() {
+        let authority = Pubkey::new_unique();
+        let (sale, bump) = pda::sale(&authority);
+        assert_eq!(
+            Pubkey::create_program_address(&[b"sale", authority.as_ref(), &[bump]], &ID).unwrap(),
+            sale
+        );
+        assert_ne!(pda::vect_vault(&sale).0, pda::usdc_treasury(&sale).0);
+        let wallet = Pubkey::new_unique();
+        assert_ne!(pda::vesting(&sale, &wallet).0, pda::grant(&sale, &wallet).0);
+    }
+
+    #[test]
+    fn claim_matches_the_program_accounts() {
+        let (sale, beneficiary, vect_mint) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = instructions::claim(sale, beneficiary, vect_mint);
+
+        assert_eq!(ix.program_id, ID);
+        assert_eq!(ix.data, vectaiproj::instruction::Claim::DISCRIMINATOR);
+        let keys: Vec<_> = ix.accounts.iter().map(|m| m.pubkey).collect();
+        assert_eq!(
+            keys[..6],
+            [
+                sale,
+                pda::vesting(&sale, &beneficiary).0,
+                beneficiary,
+                vect_mint,
+                get_associated_token_address(&beneficiary, &vect_mint),
+                pda::vect_vault(&sale).0,
+            ]
+        );
+        let signers: Vec<_> = ix.accounts.iter().filter(|m| m.is_signer).map(|m| m.pubkey).collect();
+        assert_eq!(signers, [beneficiary]);
+    }
+
+    #[test]
+    fn admin_builders_encode_arguments() {
+        let authority = Pubkey::new_unique();
+        let ix = instructions::update_price(authority, 250_000);
+        assert_eq!(ix.accounts[0].pubkey, pda::sale(&authority).0);
+        assert!(ix.accounts[1].is_signer);
+        assert_eq!(ix.data[..8], *vectaiproj::instruction::UpdatePrice::DISCRIMINATOR);
+        assert_eq!(ix.data[8..], 250_000u64.to_le_bytes());
+
+        let ix = instructions::initialize_sale(
+            authority,
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            90,
+            365,
+            50_000,
+        );
+        assert_eq!(ix.data.len(), 8 + 8 + 8 + 8);
+    }
+
+    #[test]
+    fn batch_claim_appends_vesting_pairs() {
+        let sale = Pubkey::new_unique();
+        let claims = [(Pubkey::new_unique(), Pubkey::new_unique()); 2];
+        let ix = instructions::batch_claim(sale, &claims);
+        assert_eq!(ix.accounts.len(), 3 + 4);
+        assert_eq!(ix.accounts[3].pubkey, pda::vesting(&sale, &claims[0].0).0);
+        assert_eq!(ix.accounts[4].pubkey, claims[0].1);
+        assert!(ix.accounts[3..].iter().all(|m| m.is_writable && !m.is_signer));
+    }
+
+    #[test]
+    fn decodes_accounts_by_discriminator() {
+        let vesting_account = Vesting {
+            beneficiary: Pubkey::new_unique(),
+            sale_state: Pubkey::new_unique(),
+            position_mint: Pubkey::default(),
+            total_vect_amount: 2_000_000_000,
+            claimed_amount: 5_479_452,
+            start_time: 1_700_000_000,
+            bump: 254,
+        };
+        let mut data = Vec::new();
+        vesting_account.try_serialize(&mut data).unwrap();
+
+        let decoded = vesting(&data).unwrap();
+        assert_eq!(decoded.beneficiary, vesting_account.beneficiary);
+        assert_eq!(decoded.claimed_amount, 5_479_452);
+        assert!(sale_state(&data).is_err());
+        assert!(grant(&data).is_err());
+    }
+}
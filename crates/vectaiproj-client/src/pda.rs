@@ -0,0 +1,52 @@
+//! Program-derived addresses of the vesting_sale program.
+//!
+//! These mirror the `seeds = [...]` constraints in `programs/vectaiproj` and
+//! are the only place Rust clients should spell the seeds out.
+
+use anchor_lang::prelude::Pubkey;
+
+pub const SALE_SEED: &[u8] = b"sale";
+pub const VECT_VAULT_SEED: &[u8] = b"vect_vault";
+pub const USDC_TREASURY_SEED: &[u8] = b"usdc_treasury";
+pub const VESTING_SEED: &[u8] = b"vesting";
+pub const POSITION_SEED: &[u8] = b"position";
+pub const GRANT_SEED: &[u8] = b"grant";
+
+/// `SaleState` of the sale run by `authority`.
+pub fn sale(authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SALE_SEED, authority.as_ref()], &vectaiproj::ID)
+}
+
+/// VECT token account the sale pays claims from.
+pub fn vect_vault(sale: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VECT_VAULT_SEED, sale.as_ref()], &vectaiproj::ID)
+}
+
+/// USDC token account purchases are paid into.
+pub fn usdc_treasury(sale: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USDC_TREASURY_SEED, sale.as_ref()], &vectaiproj::ID)
+}
+
+/// `Vesting` of a wallet's purchases in `sale`.
+pub fn vesting(sale: &Pubkey, beneficiary: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VESTING_SEED, sale.as_ref(), beneficiary.as_ref()],
+        &vectaiproj::ID,
+    )
+}
+
+/// `Vesting` of a position NFT bought with `buy_position_with_usdc`.
+pub fn position(sale: &Pubkey, position_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[POSITION_SEED, sale.as_ref(), position_mint.as_ref()],
+        &vectaiproj::ID,
+    )
+}
+
+/// `Grant` created for `beneficiary` by the sale authority.
+pub fn grant(sale: &Pubkey, beneficiary: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[GRANT_SEED, sale.as_ref(), beneficiary.as_ref()],
+        &vectaiproj::ID,
+    )
+}
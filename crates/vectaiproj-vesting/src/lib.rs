@@ -0,0 +1,288 @@
+//! Vesting math shared by the vesting_sale program and its clients.
+//!
+//! `programs/vectaiproj` pays claims with [`Schedule::vested_at`], so anything
+//! built on this crate (backend, CLI, or the browser through the `wasm`
+//! feature) shows exactly what `claim` would transfer. All arithmetic is
+//! checked; `None` means the inputs overflow, which the program reports as
+//! `MathOverflow`.
+
+/// Linear vesting of `total_amount` from `start_time`: nothing before the
+/// cliff, then a linear unlock over `vesting_duration` seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    pub total_amount: u64,
+    pub start_time: i64,
+    pub cliff_duration: i64,
+    pub vesting_duration: i64,
+}
+
+/// A grant's schedule. Revoking a grant cuts `total_amount` down to what had
+/// vested at that moment, after which the grant is frozen at that amount.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GrantSchedule {
+    pub schedule: Schedule,
+    pub is_revoked: bool,
+}
+
+/// Where a position stands at a given time.
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Status {
+    pub vested: u64,
+    /// What a claim would pay right now.
+    pub claimable: u64,
+    /// Next time more becomes vested; `None` once fully vested.
+    pub next_unlock: Option<i64>,
+}
+
+/// One point of an unlock timeline.
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Unlock {
+    pub time: i64,
+    /// Total vested at `time`, not the increment.
+    pub vested: u64,
+}
+
+impl Schedule {
+    /// Amount vested at `now`.
+    ///
+    /// Rounds down during the linear phase; once the schedule is over the full
+    /// amount is returned so no rounding dust is left behind.
+    pub fn vested_at(&self, now: i64) -> Option<u64> {
+        let elapsed = now.checked_sub(self.start_time)?;
+        if elapsed < self.cliff_duration {
+            return Some(0);
+        }
+
+        let vesting_end = self.cliff_duration.checked_add(self.vesting_duration)?;
+        if elapsed >= vesting_end {
+            return Some(self.total_amount);
+        }
+
+        let vesting_elapsed = elapsed - self.cliff_duration;
+        let vested = (self.total_amount as u128)
+            .checked_mul(vesting_elapsed as u128)?
+            .checked_div(self.vesting_duration as u128)?;
+        u64::try_from(vested).ok()
+    }
+
+    /// What a claim at `now` pays once `claimed` was already paid out.
+    pub fn claimable_at(&self, claimed: u64, now: i64) -> Option<u64> {
+        self.vested_at(now)?.checked_sub(claimed)
+    }
+
+    /// When the linear unlock starts.
+    pub fn cliff_end(&self) -> Option<i64> {
+        self.start_time.checked_add(self.cliff_duration)
+    }
+
+    /// When everything is vested.
+    pub fn end(&self) -> Option<i64> {
+        self.cliff_end()?.checked_add(self.vesting_duration.max(0))
+    }
+
+    /// First time after `now` at which the vested amount grows, or `None`
+    /// once everything is vested (or on overflow).
+    pub fn next_unlock(&self, now: i64) -> Option<i64> {
+        let vested = self.vested_at(now)?;
+        if vested >= self.total_amount {
+            return None;
+        }
+
+        // Smallest elapsed-since-cliff `e` with total * e / duration > vested.
+        let duration = self.vesting_duration.max(0);
+        let needed = (vested as u128 + 1) * duration as u128;
+        let e = needed.div_ceil(self.total_amount as u128);
+        let e = i64::try_from(e).ok()?.min(duration);
+        self.cliff_end()?.checked_add(e)
+    }
+
+    /// Vested, claimable and next unlock at `now`.
+    pub fn status(&self, claimed: u64, now: i64) -> Option<Status> {
+        let vested = self.vested_at(now)?;
+        Some(Status {
+            vested,
+            claimable: vested.checked_sub(claimed)?,
+            next_unlock: self.next_unlock(now),
+        })
+    }
+
+    /// Vested amount at the cliff, then every `interval` seconds, then at the
+    /// end of the schedule. `None` if `interval` is not positive.
+    ///
+    /// The point count is `vesting_duration / interval`; pick an interval
+    /// that suits the display (a day or a week for the program's limits).
+    pub fn timeline(&self, interval: i64) -> Option<Vec<Unlock>> {
+        if interval <= 0 {
+            return None;
+        }
+        let end = self.end()?;
+        let mut points = Vec::new();
+        let mut time = self.cliff_end()?;
+        while time < end {
+            points.push(Unlock { time, vested: self.vested_at(time)? });
+            time = time.checked_add(interval)?;
+        }
+        points.push(Unlock { time: end, vested: self.vested_at(end)? });
+        Some(points)
+    }
+}
+
+impl GrantSchedule {
+    /// Amount vested at `now`; all of `total_amount` once revoked.
+    pub fn vested_at(&self, now: i64) -> Option<u64> {
+        if self.is_revoked {
+            return Some(self.schedule.total_amount);
+        }
+        self.schedule.vested_at(now)
+    }
+
+    /// What a grant claim at `now` pays once `claimed` was already paid out.
+    pub fn claimable_at(&self, claimed: u64, now: i64) -> Option<u64> {
+        self.vested_at(now)?.checked_sub(claimed)
+    }
+
+    /// Vested, claimable and next unlock at `now`; a revoked grant has no
+    /// further unlocks.
+    pub fn status(&self, claimed: u64, now: i64) -> Option<Status> {
+        let vested = self.vested_at(now)?;
+        Some(Status {
+            vested,
+            claimable: vested.checked_sub(claimed)?,
+            next_unlock: if self.is_revoked { None } else { self.schedule.next_unlock(now) },
+        })
+    }
+}
+
+#[cfg(feature = "wasm")]
+mod wasm {
+    use super::*;
+    use wasm_bindgen::prelude::*;
+
+    /// `Schedule::status` for JavaScript; amounts and times are `bigint`s.
+    #[wasm_bindgen(js_name = vestingStatus)]
+    pub fn vesting_status(
+        total_amount: u64,
+        claimed_amount: u64,
+        start_time: i64,
+        cliff_duration: i64,
+        vesting_duration: i64,
+        now: i64,
+    ) -> Option<Status> {
+        Schedule { total_amount, start_time, cliff_duration, vesting_duration }
+            .status(claimed_amount, now)
+    }
+
+    /// `Schedule::timeline` for JavaScript.
+    #[wasm_bindgen(js_name = vestingTimeline)]
+    pub fn vesting_timeline(
+        total_amount: u64,
+        start_time: i64,
+        cliff_duration: i64,
+        vesting_duration: i64,
+        interval: i64,
+    ) -> Option<Vec<Unlock>> {
+        Schedule { total_amount, start_time, cliff_duration, vesting_duration }.timeline(interval)
+    }
+
+    /// `GrantSchedule::status` for JavaScript.
+    #[wasm_bindgen(js_name = grantStatus)]
+    pub fn grant_status(
+        total_amount: u64,
+        claimed_amount: u64,
+        start_time: i64,
+        cliff_duration: i64,
+        vesting_duration: i64,
+        is_revoked: bool,
+        now: i64,
+    ) -> Option<Status> {
+        let schedule = Schedule { total_amount, start_time, cliff_duration, vesting_duration };
+        GrantSchedule { schedule, is_revoked }.status(claimed_amount, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+    const START: i64 = 1_700_000_000;
+
+    fn schedule(total_amount: u64) -> Schedule {
+        Schedule {
+            total_amount,
+            start_time: START,
+            cliff_duration: 90 * DAY,
+            vesting_duration: 365 * DAY,
+        }
+    }
+
+    #[test]
+    fn next_unlock_is_the_first_second_the_amount_grows() {
+        let s = schedule(2_000_000_000);
+        let cliff_end = START + 90 * DAY;
+        // 31_536_000 / 2_000_000_000 rounds up to one second.
+        assert_eq!(s.next_unlock(START), Some(cliff_end + 1));
+        assert_eq!(s.next_unlock(cliff_end), Some(cliff_end + 1));
+
+        // With fewer tokens than seconds, unlocks are spaced out.
+        let s = schedule(365);
+        assert_eq!(s.next_unlock(cliff_end), Some(cliff_end + DAY));
+        assert_eq!(s.next_unlock(cliff_end + DAY - 1), Some(cliff_end + DAY));
+        for now in [START - 1, cliff_end, cliff_end + 1, cliff_end + 100 * DAY + 7] {
+            let next = s.next_unlock(now).unwrap();
+            assert!(s.vested_at(next).unwrap() > s.vested_at(now).unwrap());
+            assert_eq!(s.vested_at(next - 1), s.vested_at(now));
+        }
+
+        assert_eq!(s.next_unlock(cliff_end + 365 * DAY), None);
+        assert_eq!(schedule(0).next_unlock(START), None);
+    }
+
+    #[test]
+    fn status_tracks_claims() {
+        let s = schedule(365);
+        let now = START + 90 * DAY + 10 * DAY;
+        assert_eq!(
+            s.status(4, now),
+            Some(Status { vested: 10, claimable: 6, next_unlock: Some(now + DAY) })
+        );
+        // Claimed more than vested is inconsistent state, not a negative claim.
+        assert_eq!(s.status(11, now), None);
+    }
+
+    #[test]
+    fn timeline_runs_from_cliff_to_end() {
+        let s = schedule(2_000_000_000);
+        let timeline = s.timeline(30 * DAY).unwrap();
+        assert_eq!(timeline.len(), 14);
+        assert_eq!(timeline[0], Unlock { time: START + 90 * DAY, vested: 0 });
+        assert_eq!(timeline[13], Unlock { time: START + 455 * DAY, vested: 2_000_000_000 });
+        assert!(timeline.windows(2).all(|w| w[0].time < w[1].time && w[0].vested <= w[1].vested));
+        assert_eq!(s.timeline(0), None);
+    }
+
+    #[test]
+    fn revoked_grants_stay_frozen() {
+        let mut grant = GrantSchedule { schedule: schedule(365), is_revoked: false };
+        let now = START + 90 * DAY + 10 * DAY;
+        assert_eq!(grant.vested_at(now), Some(10));
+
+        // What `revoke_grant` does: keep what vested, drop the rest.
+        grant.schedule.total_amount = 10;
+        grant.is_revoked = true;
+        assert_eq!(grant.vested_at(START + 1_000 * DAY), Some(10));
+        assert_eq!(
+            grant.status(4, now + DAY),
+            Some(Status { vested: 10, claimable: 6, next_unlock: None })
+        );
+    }
+
+    #[test]
+    fn overflow_is_none() {
+        let s = Schedule { start_time: i64::MIN, ..schedule(1) };
+        assert_eq!(s.vested_at(i64::MAX), None);
+        assert_eq!(s.status(0, i64::MAX), None);
+    }
+}
@@ -1,35 +1,703 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::{instruction::Instruction, program::invoke};
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
 
 // NOTE: Replace this id with the pubkey of the keypair you generate for mock_swap.
 declare_id!("31JXctg1sE4KsKobegoWmTMcRWCbVGRZv7N58w8MFDwJ");
 
+/// Fees are expressed in basis points of the input amount.
+pub const FEE_DENOMINATOR: u64 = 10_000;
+/// Decimals of the LP mint.
+pub const LP_DECIMALS: u8 = 6;
+/// Raydium AMM v4 `swap_base_in` instruction tag.
+pub const RAYDIUM_SWAP_BASE_IN: u8 = 9;
+/// Raydium CLMM `swap` discriminator (`sha256("global:swap")[..8]`).
+pub const RAYDIUM_CLMM_SWAP: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+/// Discriminator of this program's own `swap`, moved off Anchor's default so
+/// Raydium CLMM `swap` data reaches `fallback`.
+pub const SWAP_DISCRIMINATOR: [u8; 8] = *b"mockswap";
+
 #[program]
 pub mod mock_swap {
     use super::*;
 
-    /// Simple token transfer acting as a "swap" for testing.
-    pub fn swap(ctx: Context<Swap>, amount: u64) -> Result<()> {
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.from.to_account_info(),
-            to: ctx.accounts.to.to_account_info(),
-            authority: ctx.accounts.authority.to_account_info(),
+    /// Create an empty x*y=k pool for `mint_a`/`mint_b` with PDA-owned reserves.
+    pub fn initialize_pool(ctx: Context<InitializePool>, fee_bps: u16) -> Result<()> {
+        require!((fee_bps as u64) < FEE_DENOMINATOR, ErrorCode::InvalidFee);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.mint_a = ctx.accounts.mint_a.key();
+        pool.mint_b = ctx.accounts.mint_b.key();
+        pool.vault_a = ctx.accounts.vault_a.key();
+        pool.vault_b = ctx.accounts.vault_b.key();
+        pool.lp_mint = ctx.accounts.lp_mint.key();
+        pool.fee_bps = fee_bps;
+        pool.authority = ctx.accounts.payer.key();
+        pool.bump = ctx.bumps.pool;
+
+        msg!("Pool initialized with {} bps fee", fee_bps);
+        Ok(())
+    }
+
+    /// Deposit both tokens for LP tokens. The first deposit sets the price and
+    /// mints `sqrt(a * b)`; later ones mint pro rata to the smaller side, so
+    /// amounts off the pool ratio donate the excess to the pool.
+    pub fn add_liquidity(
+        ctx: Context<Liquidity>,
+        amount_a: u64,
+        amount_b: u64,
+        min_lp_out: u64,
+    ) -> Result<()> {
+        require!(amount_a > 0 && amount_b > 0, ErrorCode::ZeroAmount);
+
+        let lp_amount = lp_for_deposit(
+            amount_a,
+            amount_b,
+            ctx.accounts.vault_a.amount,
+            ctx.accounts.vault_b.amount,
+            ctx.accounts.lp_mint.supply,
+        )
+        .ok_or(ErrorCode::MathOverflow)?;
+        require!(lp_amount > 0, ErrorCode::ZeroAmount);
+        require!(lp_amount >= min_lp_out, ErrorCode::SlippageExceeded);
+
+        let token_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                Transfer {
+                    from: ctx.accounts.user_a.to_account_info(),
+                    to: ctx.accounts.vault_a.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount_a,
+        )?;
+        token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                Transfer {
+                    from: ctx.accounts.user_b.to_account_info(),
+                    to: ctx.accounts.vault_b.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount_b,
+        )?;
+
+        let pool = &ctx.accounts.pool;
+        let seeds = &[b"pool".as_ref(), pool.mint_a.as_ref(), pool.mint_b.as_ref(), &[pool.bump]];
+        token::mint_to(
+            CpiContext::new_with_signer(
+                token_program,
+                MintTo {
+                    mint: ctx.accounts.lp_mint.to_account_info(),
+                    to: ctx.accounts.user_lp.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            lp_amount,
+        )?;
+
+        msg!("Added {} / {} for {} LP", amount_a, amount_b, lp_amount);
+        Ok(())
+    }
+
+    /// Burn LP tokens for a pro-rata share of both reserves.
+    pub fn remove_liquidity(
+        ctx: Context<Liquidity>,
+        lp_amount: u64,
+        min_amount_a: u64,
+        min_amount_b: u64,
+    ) -> Result<()> {
+        require!(lp_amount > 0, ErrorCode::ZeroAmount);
+
+        let supply = ctx.accounts.lp_mint.supply;
+        let amount_a = pro_rata(ctx.accounts.vault_a.amount, lp_amount, supply)
+            .ok_or(ErrorCode::MathOverflow)?;
+        let amount_b = pro_rata(ctx.accounts.vault_b.amount, lp_amount, supply)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            amount_a >= min_amount_a && amount_b >= min_amount_b,
+            ErrorCode::SlippageExceeded
+        );
+
+        let token_program = ctx.accounts.token_program.to_account_info();
+        token::burn(
+            CpiContext::new(
+                token_program.clone(),
+                Burn {
+                    mint: ctx.accounts.lp_mint.to_account_info(),
+                    from: ctx.accounts.user_lp.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            lp_amount,
+        )?;
+
+        let pool = &ctx.accounts.pool;
+        let seeds = &[b"pool".as_ref(), pool.mint_a.as_ref(), pool.mint_b.as_ref(), &[pool.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                Transfer {
+                    from: ctx.accounts.vault_a.to_account_info(),
+                    to: ctx.accounts.user_a.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount_a,
+        )?;
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program,
+                Transfer {
+                    from: ctx.accounts.vault_b.to_account_info(),
+                    to: ctx.accounts.user_b.to_account_info(),
+                    authority: ctx.accounts.pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount_b,
+        )?;
+
+        msg!("Removed {} LP for {} / {}", lp_amount, amount_a, amount_b);
+        Ok(())
+    }
+
+    /// Swap exactly `amount_in` of `user_source` for at least `min_amount_out`
+    /// into `user_destination`. The direction follows the source mint.
+    ///
+    /// `remaining_accounts[0]`, when present, is the pool's `FailureConfig`;
+    /// `FailureMode::Reenter` also needs its target program after it.
+    #[instruction(discriminator = &SWAP_DISCRIMINATOR)]
+    pub fn swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, Swap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<()> {
+        let mode = failure_mode(&ctx.accounts.pool.key(), ctx.remaining_accounts)?;
+        ctx.accounts.process(amount_in, min_amount_out, mode, ctx.remaining_accounts)
+    }
+
+    /// Create the pool's failure-injection config, controlled by `authority`,
+    /// who must be the pool's creator. It starts disabled and only affects
+    /// swaps that pass it.
+    pub fn initialize_failure_config(ctx: Context<InitializeFailureConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.failure_config;
+        config.pool = ctx.accounts.pool.key();
+        config.authority = ctx.accounts.authority.key();
+        config.mode = FailureMode::Disabled;
+        config.bump = ctx.bumps.failure_config;
+        Ok(())
+    }
+
+    /// Select how swaps that pass this config misbehave.
+    pub fn set_failure_mode(ctx: Context<SetFailureMode>, mode: FailureMode) -> Result<()> {
+        if let FailureMode::ShortOutput { output_bps } = mode {
+            require!((output_bps as u64) < FEE_DENOMINATOR, ErrorCode::InvalidFailureMode);
+        }
+        ctx.accounts.failure_config.mode = mode;
+        msg!("Failure mode set to {:?}", mode);
+        Ok(())
+    }
+
+    /// Raydium compatibility: non-Anchor instruction data lands here, so
+    /// AMM v4 `swap_base_in` and exact-input CLMM `swap` instructions built by
+    /// Raydium client code run against the pool.
+    pub fn fallback<'info>(
+        _program_id: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+        data: &[u8],
+    ) -> Result<()> {
+        if let Some((&RAYDIUM_SWAP_BASE_IN, args)) = data.split_first() {
+            if args.len() == 16 {
+                let amount_in = u64::from_le_bytes(args[..8].try_into().unwrap());
+                let min_amount_out = u64::from_le_bytes(args[8..].try_into().unwrap());
+                return Swap::from_raydium_accounts(accounts)?.process(
+                    amount_in,
+                    min_amount_out,
+                    FailureMode::Disabled,
+                    &[],
+                );
+            }
+        }
+        // `amount`, `other_amount_threshold`, `sqrt_price_limit_x64` (ignored)
+        // and `is_base_input`.
+        if let Some(args) = data.strip_prefix(&RAYDIUM_CLMM_SWAP) {
+            if args.len() == 33 {
+                require!(args[32] == 1, ErrorCode::ExactOutputUnsupported);
+                let amount_in = u64::from_le_bytes(args[..8].try_into().unwrap());
+                let min_amount_out = u64::from_le_bytes(args[8..16].try_into().unwrap());
+                return Swap::from_clmm_accounts(accounts)?.process(
+                    amount_in,
+                    min_amount_out,
+                    FailureMode::Disabled,
+                    &[],
+                );
+            }
+        }
+        Err(anchor_lang::error::ErrorCode::InstructionFallbackNotFound.into())
+    }
+}
+
+impl<'info> Swap<'info> {
+    /// Map Raydium AMM v4 `swap_base_in` accounts onto `Swap`: the AMM account
+    /// is the pool and the coin/PC vaults are `vault_a`/`vault_b`. Both the
+    /// 18-account layout and the 17-account one without `amm_target_orders`
+    /// are accepted; the market accounts are ignored.
+    fn from_raydium_accounts(accounts: &'info [AccountInfo<'info>]) -> Result<Self> {
+        let len = accounts.len();
+        require!(len == 17 || len == 18, ErrorCode::InvalidRaydiumAccounts);
+
+        Swap {
+            authority: Signer::try_from(&accounts[len - 1])?,
+            pool: Box::new(Account::try_from(&accounts[1])?),
+            user_source: Box::new(Account::try_from(&accounts[len - 3])?),
+            user_destination: Box::new(Account::try_from(&accounts[len - 2])?),
+            vault_a: Box::new(Account::try_from(&accounts[len - 13])?),
+            vault_b: Box::new(Account::try_from(&accounts[len - 12])?),
+            token_program: Program::try_from(&accounts[0])?,
+        }
+        .checked()
+    }
+
+    /// Map Raydium CLMM `swap` accounts onto `Swap`: the pool state is the
+    /// pool and the input/output vaults are its reserves, in either order.
+    /// The config, observation state and tick arrays (at least one) are ignored.
+    fn from_clmm_accounts(accounts: &'info [AccountInfo<'info>]) -> Result<Self> {
+        require!(accounts.len() >= 10, ErrorCode::InvalidRaydiumAccounts);
+
+        let pool: Box<Account<Pool>> = Box::new(Account::try_from(&accounts[2])?);
+        let (vault_a, vault_b) = if *accounts[5].key == pool.vault_a {
+            (&accounts[5], &accounts[6])
+        } else {
+            (&accounts[6], &accounts[5])
         };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token::transfer(cpi_ctx, amount)?;
+        Swap {
+            authority: Signer::try_from(&accounts[0])?,
+            pool,
+            user_source: Box::new(Account::try_from(&accounts[3])?),
+            user_destination: Box::new(Account::try_from(&accounts[4])?),
+            vault_a: Box::new(Account::try_from(vault_a)?),
+            vault_b: Box::new(Account::try_from(vault_b)?),
+            token_program: Program::try_from(&accounts[8])?,
+        }
+        .checked()
+    }
+
+    /// Constraints `Swap` declares for the Anchor entry point.
+    fn checked(self) -> Result<Self> {
+        require_keys_eq!(self.pool.vault_a, self.vault_a.key(), ErrorCode::InvalidRaydiumAccounts);
+        require_keys_eq!(self.pool.vault_b, self.vault_b.key(), ErrorCode::InvalidRaydiumAccounts);
+        require_keys_eq!(
+            self.user_source.owner,
+            self.authority.key(),
+            ErrorCode::InvalidRaydiumAccounts
+        );
+        Ok(self)
+    }
+
+    fn process(
+        &self,
+        amount_in: u64,
+        min_amount_out: u64,
+        mode: FailureMode,
+        remaining: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(amount_in > 0, ErrorCode::ZeroAmount);
+
+        match mode {
+            FailureMode::Disabled | FailureMode::ShortOutput { .. } => {}
+            FailureMode::Error { code } => {
+                msg!("Injected failure: error {}", code);
+                return Err(ProgramError::Custom(code).into());
+            }
+            FailureMode::BurnCompute { iterations } => {
+                msg!("Injected failure: burning compute for {} iterations", iterations);
+                burn_compute(iterations);
+            }
+            FailureMode::Reenter { program } => {
+                msg!("Injected failure: re-entering {}", program);
+                let target = remaining
+                    .iter()
+                    .find(|account| account.key() == program)
+                    .ok_or(ErrorCode::InvalidFailureConfig)?;
+                let ix = Instruction { program_id: program, accounts: vec![], data: vec![] };
+                invoke(&ix, std::slice::from_ref(target))?;
+            }
+        }
+
+        let pool = &self.pool;
+        let source_mint = self.user_source.mint;
+        let destination_mint = self.user_destination.mint;
+        let (vault_in, vault_out) = if source_mint == pool.mint_a && destination_mint == pool.mint_b {
+            (&self.vault_a, &self.vault_b)
+        } else if source_mint == pool.mint_b && destination_mint == pool.mint_a {
+            (&self.vault_b, &self.vault_a)
+        } else {
+            return err!(ErrorCode::InvalidMint);
+        };
+
+        let amount_out = swap_amount_out(amount_in, vault_in.amount, vault_out.amount, pool.fee_bps)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(amount_out > 0, ErrorCode::InsufficientLiquidity);
+        let amount_out = match mode {
+            // A misbehaving pool: pays short and ignores the caller's minimum.
+            FailureMode::ShortOutput { output_bps } => {
+                pro_rata(amount_out, output_bps as u64, FEE_DENOMINATOR).ok_or(ErrorCode::MathOverflow)?
+            }
+            _ => {
+                require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+                amount_out
+            }
+        };
+
+        let token_program = self.token_program.to_account_info();
+        token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                Transfer {
+                    from: self.user_source.to_account_info(),
+                    to: vault_in.to_account_info(),
+                    authority: self.authority.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+
+        let seeds = &[b"pool".as_ref(), pool.mint_a.as_ref(), pool.mint_b.as_ref(), &[pool.bump]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program,
+                Transfer {
+                    from: vault_out.to_account_info(),
+                    to: self.user_destination.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount_out,
+        )?;
+
+        msg!("Swapped {} for {}", amount_in, amount_out);
         Ok(())
     }
 }
 
+/// Mode from the optional `FailureConfig` at `remaining[0]`.
+fn failure_mode(pool: &Pubkey, remaining: &[AccountInfo]) -> Result<FailureMode> {
+    let Some(info) = remaining.first() else {
+        return Ok(FailureMode::Disabled);
+    };
+    require_keys_eq!(*info.owner, crate::ID, ErrorCode::InvalidFailureConfig);
+    let config = FailureConfig::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    require_keys_eq!(config.pool, *pool, ErrorCode::InvalidFailureConfig);
+    Ok(config.mode)
+}
+
+/// Spin without side effects so the caller's compute budget runs out.
+fn burn_compute(iterations: u32) {
+    let mut acc = 0u64;
+    for i in 0..iterations {
+        acc = std::hint::black_box(acc.wrapping_mul(31).wrapping_add(i as u64));
+    }
+    msg!("Burned compute: {}", acc);
+}
+
+// ============================================================================
+// Pool Math
+// ============================================================================
+
+/// Output of a constant-product swap after taking `fee_bps` of the input.
+pub fn swap_amount_out(amount_in: u64, reserve_in: u64, reserve_out: u64, fee_bps: u16) -> Option<u64> {
+    let amount_in_after_fee = (amount_in as u128)
+        .checked_mul((FEE_DENOMINATOR - fee_bps as u64) as u128)?
+        / FEE_DENOMINATOR as u128;
+    let numerator = amount_in_after_fee.checked_mul(reserve_out as u128)?;
+    let denominator = (reserve_in as u128).checked_add(amount_in_after_fee)?;
+    if denominator == 0 {
+        return Some(0);
+    }
+    u64::try_from(numerator / denominator).ok()
+}
+
+/// LP tokens minted for depositing `amount_a`/`amount_b`.
+pub fn lp_for_deposit(
+    amount_a: u64,
+    amount_b: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    lp_supply: u64,
+) -> Option<u64> {
+    if lp_supply == 0 {
+        let product = (amount_a as u128).checked_mul(amount_b as u128)?;
+        return u64::try_from(isqrt(product)).ok();
+    }
+    if reserve_a == 0 || reserve_b == 0 {
+        return None;
+    }
+    let from_a = pro_rata(lp_supply, amount_a, reserve_a)?;
+    let from_b = pro_rata(lp_supply, amount_b, reserve_b)?;
+    Some(from_a.min(from_b))
+}
+
+/// `amount * numerator / denominator`, rounded down.
+fn pro_rata(amount: u64, numerator: u64, denominator: u64) -> Option<u64> {
+    if denominator == 0 {
+        return None;
+    }
+    let value = (amount as u128).checked_mul(numerator as u128)? / denominator as u128;
+    u64::try_from(value).ok()
+}
+
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+// ============================================================================
+// Accounts
+// ============================================================================
+
 #[derive(Accounts)]
-pub struct Swap<'info> {
+pub struct InitializePool<'info> {
     #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = mint_a.key() != mint_b.key() @ ErrorCode::InvalidMint)]
+    pub mint_a: Box<Account<'info, Mint>>,
+    pub mint_b: Box<Account<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [b"pool", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_a,
+        token::authority = pool,
+        seeds = [b"vault", pool.key().as_ref(), mint_a.key().as_ref()],
+        bump
+    )]
+    pub vault_a: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_b,
+        token::authority = pool,
+        seeds = [b"vault", pool.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub vault_b: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        mint::decimals = LP_DECIMALS,
+        mint::authority = pool,
+        seeds = [b"lp_mint", pool.key().as_ref()],
+        bump
+    )]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Liquidity<'info> {
+    pub user: Signer<'info>,
+
+    #[account(has_one = vault_a, has_one = vault_b, has_one = lp_mint)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut)]
+    pub vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub vault_b: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub lp_mint: Box<Account<'info, Mint>>,
+
+    #[account(mut, token::mint = pool.mint_a, token::authority = user)]
+    pub user_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = pool.mint_b, token::authority = user)]
+    pub user_b: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = lp_mint, token::authority = user)]
+    pub user_lp: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
     pub authority: Signer<'info>,
+
+    #[account(has_one = vault_a, has_one = vault_b)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(mut, token::authority = authority)]
+    pub user_source: Box<Account<'info, TokenAccount>>,
     #[account(mut)]
-    pub from: Account<'info, TokenAccount>,
+    pub user_destination: Box<Account<'info, TokenAccount>>,
+
     #[account(mut)]
-    pub to: Account<'info, TokenAccount>,
+    pub vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub vault_b: Box<Account<'info, TokenAccount>>,
+
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeFailureConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(has_one = authority)]
+    pub pool: Box<Account<'info, Pool>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + FailureConfig::INIT_SPACE,
+        seeds = [b"failure", pool.key().as_ref()],
+        bump
+    )]
+    pub failure_config: Account<'info, FailureConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFailureMode<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = authority)]
+    pub failure_config: Account<'info, FailureConfig>,
+}
+
+// ============================================================================
+// State & Errors
+// ============================================================================
+
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub lp_mint: Pubkey,
+    pub fee_bps: u16,
+    /// Pool creator; the only one who may set up failure injection.
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+/// Failure injected into swaps that pass the pool's `FailureConfig`, for
+/// exercising callers' error paths.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum FailureMode {
+    /// Swap normally.
+    Disabled,
+    /// Fail with `ProgramError::Custom(code)`.
+    Error { code: u32 },
+    /// Pay `output_bps` of the quoted output and ignore `min_amount_out`.
+    ShortOutput { output_bps: u16 },
+    /// Spin for `iterations` before swapping.
+    BurnCompute { iterations: u32 },
+    /// CPI into `program` before swapping; it must follow the config in the
+    /// remaining accounts. Targeting the calling program trips the runtime's
+    /// reentrancy check.
+    Reenter { program: Pubkey },
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FailureConfig {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub mode: FailureMode,
+    pub bump: u8,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Fee must be below 10000 bps")]
+    InvalidFee,
+
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+
+    #[msg("Token accounts do not match the pool mints")]
+    InvalidMint,
+
+    #[msg("Output is below the requested minimum")]
+    SlippageExceeded,
+
+    #[msg("Pool cannot cover this swap")]
+    InsufficientLiquidity,
+
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+
+    #[msg("Accounts do not match the Raydium swap layout")]
+    InvalidRaydiumAccounts,
+
+    #[msg("Failure config does not belong to this pool or is missing accounts")]
+    InvalidFailureConfig,
+
+    #[msg("Short output must be below 10000 bps")]
+    InvalidFailureMode,
+
+    #[msg("Only exact-input CLMM swaps are supported")]
+    ExactOutputUnsupported,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_output_follows_constant_product() {
+        // No fee: 100 in against 1000/1000 -> 1000 * 100 / 1100.
+        assert_eq!(swap_amount_out(100, 1_000, 1_000, 0), Some(90));
+        // 30 bps fee leaves 99 (rounded down) of 100 in.
+        assert_eq!(swap_amount_out(100, 1_000, 1_000, 30), Some(90));
+        assert_eq!(swap_amount_out(1_000_000, 1_000_000_000, 1_000_000_000, 30), Some(996_006));
+        assert_eq!(swap_amount_out(100, 0, 0, 0), Some(0));
+    }
+
+    #[test]
+    fn lp_minting() {
+        assert_eq!(lp_for_deposit(400, 100, 0, 0, 0), Some(200));
+        // Pro rata to the smaller side.
+        assert_eq!(lp_for_deposit(40, 20, 400, 100, 200), Some(20));
+        assert_eq!(lp_for_deposit(1, 1, 0, 100, 200), None);
+        assert_eq!(isqrt(u64::MAX as u128 * u64::MAX as u128), u64::MAX as u128);
+    }
+
+    #[test]
+    fn raydium_clmm_swap_reaches_the_fallback() {
+        assert_eq!(instruction::Swap::DISCRIMINATOR, SWAP_DISCRIMINATOR);
+        assert_ne!(instruction::Swap::DISCRIMINATOR, RAYDIUM_CLMM_SWAP);
+    }
+}
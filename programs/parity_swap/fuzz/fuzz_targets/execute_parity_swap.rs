@@ -0,0 +1,457 @@
+//! Fuzz `execute_parity_swap` end to end: the SBF builds of parity_swap and
+//! mock_swap from `anchor build` running in litesvm, with random numbers,
+//! strategies and routes (well-formed mock_swap swaps, raw instruction data,
+//! arbitrary account lists with random signer/writable flags).
+//!
+//! Every route's forwarded metas mark only the authority as signer, and
+//! every transaction either fails inside the router's instruction or
+//! succeeds with the strategy's route, which paid at least its minimum into
+//! its destination, moved no tokens outside its own accounts, created none,
+//! and took nothing from the second wallet, even when it co-signed.
+//!
+//! Needs the SBF builds: run `scripts/build-programs.sh mock_swap
+//! parity_swap` from the repository root before `cargo fuzz run`.
+
+#![no_main]
+
+use std::cell::RefCell;
+
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{system_program, AccountSerialize, InstructionData, Space, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use litesvm::LiteSVM;
+use parity_swap::{RouterConfig, Strategy, SwapRoute, MAX_INPUTS, MAX_ROUTES};
+use solana_sdk::account::Account;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+const DEPLOY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../../target/deploy");
+
+const RESERVE: u64 = 1_000_000_000;
+const WALLET_BALANCE: u64 = 1_000_000;
+const FEE_BPS: u16 = 30;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    numbers: Vec<i64>,
+    strategy: FuzzStrategy,
+    weighted_target: i64,
+    routes: Vec<FuzzRoute>,
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzStrategy {
+    Parity,
+    Threshold(i64),
+    Sign,
+    WeightedTarget(Vec<i64>),
+    Modulo,
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzRoute {
+    ix: FuzzIx,
+    accounts: FuzzAccounts,
+    /// Overrides the number of accounts the route declares.
+    accounts_len: Option<u8>,
+    /// Overrides the destination (the swap's `user_destination` otherwise).
+    destination_index: Option<u8>,
+    min_amount_out: u64,
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzIx {
+    /// mock_swap's own `swap`.
+    Swap { amount_in: u64, min_amount_out: u64 },
+    Raw(Vec<u8>),
+}
+
+#[derive(Arbitrary, Debug)]
+enum FuzzAccounts {
+    /// mock_swap `Swap` accounts, spending the authority's or the other
+    /// wallet's tokens, in either direction.
+    Swap { a_to_b: bool, from_other: bool, other_signs: bool },
+    Custom(Vec<FuzzAccount>),
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzAccount {
+    /// Index into `Env::keys`.
+    key: u8,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+/// parity_swap and mock_swap loaded in one VM, with an a/b pool and token
+/// accounts for the swapping authority and a second wallet.
+struct Env {
+    svm: LiteSVM,
+    authority: Keypair,
+    other: Keypair,
+    config: Pubkey,
+    rate_limit: Pubkey,
+    pool: Pubkey,
+    vault_a: Pubkey,
+    vault_b: Pubkey,
+    /// Accounts of (authority, other) in mint a and mint b.
+    wallets: [[Pubkey; 2]; 2],
+    mints: [Pubkey; 2],
+}
+
+impl Env {
+    fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        for (id, name) in [(parity_swap::ID, "parity_swap"), (mock_swap::ID, "mock_swap")] {
+            let path = format!("{DEPLOY}/{name}.so");
+            svm.add_program_from_file(id, &path)
+                .unwrap_or_else(|err| panic!("{path}: {err}, run `scripts/build-programs.sh mock_swap parity_swap` first"));
+        }
+
+        let (authority, other) = (Keypair::new(), Keypair::new());
+        for wallet in [&authority, &other] {
+            svm.airdrop(&wallet.pubkey(), 1_000_000_000_000).unwrap();
+        }
+
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let (pool, bump) =
+            Pubkey::find_program_address(&[b"pool", mints[0].as_ref(), mints[1].as_ref()], &mock_swap::ID);
+        let mut env = Env {
+            svm,
+            config: Pubkey::find_program_address(&[b"config"], &parity_swap::ID).0,
+            rate_limit: Pubkey::find_program_address(
+                &[b"rate_limit", authority.pubkey().as_ref()],
+                &parity_swap::ID,
+            )
+            .0,
+            pool,
+            vault_a: Pubkey::new_unique(),
+            vault_b: Pubkey::new_unique(),
+            wallets: [
+                [Pubkey::new_unique(), Pubkey::new_unique()],
+                [Pubkey::new_unique(), Pubkey::new_unique()],
+            ],
+            mints,
+            authority,
+            other,
+        };
+        for mint in mints {
+            env.create_mint(mint);
+        }
+        let pool = mock_swap::Pool {
+            mint_a: mints[0],
+            mint_b: mints[1],
+            vault_a: env.vault_a,
+            vault_b: env.vault_b,
+            lp_mint: Pubkey::new_unique(),
+            fee_bps: FEE_BPS,
+            authority: Pubkey::new_unique(),
+            bump,
+        };
+        env.set_anchor_account(env.pool, mock_swap::ID, &pool, mock_swap::Pool::INIT_SPACE);
+        env
+    }
+
+    /// Token balances and the config back at their starting values.
+    fn reset(&mut self, weighted_target: i64) {
+        for (mint, [authority, other]) in self.mints.into_iter().zip(self.wallets) {
+            self.set_token_account(authority, mint, self.authority.pubkey(), WALLET_BALANCE);
+            self.set_token_account(other, mint, self.other.pubkey(), WALLET_BALANCE);
+        }
+        self.set_token_account(self.vault_a, self.mints[0], self.pool, RESERVE);
+        self.set_token_account(self.vault_b, self.mints[1], self.pool, RESERVE);
+
+        let config = RouterConfig {
+            admin: Pubkey::default(),
+            allowed_programs: vec![mock_swap::ID],
+            weighted_target,
+            cooldown_slots: 0,
+            rate_window_slots: 0,
+            max_window_volume: 0,
+            volume_mint: Pubkey::default(),
+            commit_deposit: 0,
+            bump: Pubkey::find_program_address(&[b"config"], &parity_swap::ID).1,
+        };
+        self.set_anchor_account(self.config, parity_swap::ID, &config, RouterConfig::INIT_SPACE);
+    }
+
+    fn create_mint(&mut self, mint: Pubkey) {
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        let state = spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply: u64::MAX,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        spl_token::state::Mint::pack(state, &mut data).unwrap();
+        self.set_account(mint, spl_token::ID, data);
+    }
+
+    fn set_token_account(&mut self, address: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64) {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        let state = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        spl_token::state::Account::pack(state, &mut data).unwrap();
+        self.set_account(address, spl_token::ID, data);
+    }
+
+    fn set_anchor_account(&mut self, address: Pubkey, owner: Pubkey, state: &impl AccountSerialize, space: usize) {
+        let mut data = Vec::with_capacity(8 + space);
+        state.try_serialize(&mut data).unwrap();
+        data.resize(8 + space, 0);
+        self.set_account(address, owner, data);
+    }
+
+    fn set_account(&mut self, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(address, account).unwrap();
+    }
+
+    /// Every key a custom route may list.
+    fn keys(&self) -> Vec<Pubkey> {
+        let mut keys = vec![
+            self.authority.pubkey(),
+            self.other.pubkey(),
+            self.pool,
+            self.vault_a,
+            self.vault_b,
+            self.config,
+            self.rate_limit,
+            spl_token::ID,
+            mock_swap::ID,
+            parity_swap::ID,
+        ];
+        keys.extend(self.mints);
+        keys.extend(self.token_accounts().map(|(key, _)| key));
+        keys
+    }
+
+    /// Token accounts the swaps can move, with their mint index.
+    fn token_accounts(&self) -> impl Iterator<Item = (Pubkey, usize)> + '_ {
+        let [[authority_a, other_a], [authority_b, other_b]] = self.wallets;
+        [
+            (authority_a, 0),
+            (other_a, 0),
+            (self.vault_a, 0),
+            (authority_b, 1),
+            (other_b, 1),
+            (self.vault_b, 1),
+        ]
+        .into_iter()
+    }
+
+    fn balance(&self, account: &Pubkey) -> u64 {
+        let account = self.svm.get_account(account).expect("token account exists");
+        spl_token::state::Account::unpack(&account.data).unwrap().amount
+    }
+
+    fn balances(&self) -> Vec<(Pubkey, usize, u64)> {
+        self.token_accounts()
+            .map(|(key, mint)| (key, mint, self.balance(&key)))
+            .collect()
+    }
+
+    /// A route's remaining accounts, marking as signer only keys that can
+    /// actually sign the transaction.
+    fn route_accounts(&self, accounts: &FuzzAccounts) -> Vec<AccountMeta> {
+        let (authority, other) = (self.authority.pubkey(), self.other.pubkey());
+        match accounts {
+            FuzzAccounts::Swap { a_to_b, from_other, other_signs } => {
+                let [from, to] = if *a_to_b { [0, 1] } else { [1, 0] };
+                let (owner, wallet) = if *from_other { (other, 1) } else { (authority, 0) };
+                let mut metas = mock_swap::accounts::Swap {
+                    authority: owner,
+                    pool: self.pool,
+                    user_source: self.wallets[from][wallet],
+                    user_destination: self.wallets[to][0],
+                    vault_a: self.vault_a,
+                    vault_b: self.vault_b,
+                    token_program: spl_token::ID,
+                }
+                .to_account_metas(None);
+                // The authority always signs; the other wallet only if asked to.
+                metas[0].is_signer = !*from_other || *other_signs;
+                if *from_other && *other_signs {
+                    metas.push(AccountMeta::new_readonly(authority, true));
+                }
+                metas
+            }
+            FuzzAccounts::Custom(accounts) => {
+                let keys = self.keys();
+                accounts
+                    .iter()
+                    .take(16)
+                    .map(|acc| {
+                        let key = keys[acc.key as usize % keys.len()];
+                        let is_signer = acc.is_signer && (key == authority || key == other);
+                        if acc.is_writable {
+                            AccountMeta::new(key, is_signer)
+                        } else {
+                            AccountMeta::new_readonly(key, is_signer)
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+thread_local! {
+    static ENV: RefCell<Env> = RefCell::new(Env::new());
+}
+
+fuzz_target!(|input: Input| {
+    ENV.with_borrow_mut(|env| run(env, input));
+});
+
+fn run(env: &mut Env, input: Input) {
+    env.reset(input.weighted_target);
+
+    let strategy = match input.strategy {
+        FuzzStrategy::Parity => Strategy::Parity,
+        FuzzStrategy::Threshold(threshold) => Strategy::Threshold { threshold },
+        FuzzStrategy::Sign => Strategy::Sign,
+        FuzzStrategy::WeightedTarget(weights) => Strategy::WeightedTarget { weights },
+        FuzzStrategy::Modulo => Strategy::Modulo,
+    };
+    let mut numbers = input.numbers;
+    numbers.truncate(MAX_INPUTS + 1);
+
+    let mut routes = Vec::new();
+    let mut route_accounts = Vec::new();
+    for route in input.routes.into_iter().take(MAX_ROUTES + 1) {
+        let accounts = env.route_accounts(&route.accounts);
+        let ix_data = match route.ix {
+            FuzzIx::Swap { amount_in, min_amount_out } => {
+                mock_swap::instruction::Swap { amount_in, min_amount_out }.data()
+            }
+            FuzzIx::Raw(mut data) => {
+                data.truncate(64);
+                data
+            }
+        };
+        routes.push(SwapRoute {
+            ix_data,
+            accounts_len: route.accounts_len.unwrap_or(accounts.len() as u8),
+            destination_index: route.destination_index.unwrap_or(3),
+            min_amount_out: route.min_amount_out,
+        });
+        route_accounts.push(accounts);
+    }
+
+    let authority = env.authority.pubkey();
+    let mut accounts = parity_swap::accounts::ParitySwap {
+        authority,
+        config: env.config,
+        rate_limit: env.rate_limit,
+        system_program: system_program::ID,
+        dex_program: mock_swap::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(route_accounts.iter().flatten().cloned());
+    let other_signs = accounts
+        .iter()
+        .any(|meta| meta.pubkey == env.other.pubkey() && meta.is_signer);
+    for route in &route_accounts {
+        assert_forwarded_signers(authority, &accounts, route);
+    }
+    let ix = Instruction {
+        program_id: parity_swap::ID,
+        accounts,
+        data: parity_swap::instruction::ExecuteParitySwap {
+            numbers: numbers.clone(),
+            strategy: strategy.clone(),
+            routes: routes.clone(),
+        }
+        .data(),
+    };
+
+    let before = env.balances();
+    let other = env.other.insecure_clone();
+    let mut signers = vec![&env.authority];
+    if other_signs {
+        signers.push(&other);
+    }
+    env.svm.expire_blockhash();
+    let blockhash = env.svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&authority), &signers[..], blockhash);
+    let result = env.svm.send_transaction(tx);
+    let after = env.balances();
+
+    if let Err(failed) = result {
+        // Rejected by the router or the DEX, never before reaching them, and
+        // rolled back in full.
+        assert!(
+            matches!(failed.err, TransactionError::InstructionError(0, _)),
+            "unexpected error {:?}",
+            failed.err
+        );
+        assert_eq!(before, after);
+        return;
+    }
+
+    // The router only succeeds with the route its strategy picks.
+    let (route_index, _) = strategy
+        .select_route(&numbers, input.weighted_target, routes.len())
+        .expect("an accepted swap has a valid strategy");
+    let route = &routes[route_index];
+    let forwarded = &route_accounts[route_index];
+    assert_eq!(route.accounts_len as usize, forwarded.len());
+
+    let destination = forwarded[route.destination_index as usize].pubkey;
+    let mut minted = [0i128; 2];
+    for ((key, mint, before), (_, _, after)) in before.iter().zip(&after) {
+        minted[*mint] += i128::from(*after) - i128::from(*before);
+        if before != after {
+            assert!(forwarded.iter().any(|meta| meta.pubkey == *key), "{key} changed outside the route");
+        }
+        if *key == destination {
+            let gain = after.checked_sub(*before);
+            assert!(gain >= Some(route.min_amount_out), "destination got less than its minimum");
+        }
+        if env.wallets.iter().any(|wallet| wallet[1] == *key) {
+            assert!(after >= before, "spent from the second wallet");
+        }
+    }
+    assert_eq!(minted, [0, 0], "tokens were created or destroyed");
+}
+
+/// Only `authority` is forwarded as signer, whatever else signs the
+/// transaction. The route's accounts get the flags the runtime would give
+/// them: a key signs or is writable if any of the instruction's metas (or the
+/// fee payer, `authority`) says so.
+fn assert_forwarded_signers(authority: Pubkey, accounts: &[AccountMeta], route: &[AccountMeta]) {
+    let any = |key: &Pubkey, flag: fn(&AccountMeta) -> bool| {
+        *key == authority || accounts.iter().any(|meta| meta.pubkey == *key && flag(meta))
+    };
+    let owner = Pubkey::default();
+    let mut lamports = vec![0u64; route.len()];
+    let infos: Vec<AccountInfo> = route
+        .iter()
+        .zip(&mut lamports)
+        .map(|(meta, lamports)| {
+            let (is_signer, is_writable) = (any(&meta.pubkey, |m| m.is_signer), any(&meta.pubkey, |m| m.is_writable));
+            AccountInfo::new(&meta.pubkey, is_signer, is_writable, lamports, &mut [], &owner, false, 0)
+        })
+        .collect();
+    for meta in parity_swap::fuzz::wallet_route_metas(&infos, authority) {
+        assert!(!meta.is_signer || meta.pubkey == authority, "forwarded {}'s signature", meta.pubkey);
+    }
+}
@@ -1,102 +1,1842 @@
+use std::ops::Range;
+
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
-use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::Owners;
+use anchor_spl::token::{self, Token, Transfer};
+use anchor_spl::token_interface::TokenAccount;
+use solana_sha256_hasher::hashv;
+
+pub mod raydium;
+
+use raydium::{AmmV4Pool, ClmmSwapAccounts};
 
 declare_id!("Gf8A8CSDftRjWCjiJPPWoM6ugnW639F78M7mwWQf2u9Q");
 
 /// Maximum number of inputs we will sum to avoid unbounded compute.
 pub const MAX_INPUTS: usize = 64;
+/// Maximum number of candidate routes in one swap.
+pub const MAX_ROUTES: usize = 8;
+/// Maximum number of DEX programs the router may forward to.
+pub const MAX_ALLOWED_PROGRAMS: usize = 16;
+/// Layout version carried by `SwapDecisionV2`.
+pub const SWAP_DECISION_VERSION: u8 = 2;
+/// Slots the `SlotHashes` sysvar keeps (`solana_slot_hashes::MAX_ENTRIES`).
+pub const SLOT_HASHES_MAX_ENTRIES: u64 = 512;
+/// Maximum number of mint pairs a vault may trade.
+pub const MAX_VAULT_LIMITS: usize = 8;
+/// Fixed-point scale of `VaultLimit::min_price` (output per input).
+pub const PRICE_SCALE: u64 = 1_000_000_000;
 
 #[program]
 pub mod parity_swap {
     use super::*;
 
-    /// Decide whether to buy or sell based on the parity of the summed inputs,
-    /// then forward the matching swap instruction (e.g., Raydium swap) via CPI.
-    pub fn execute_parity_swap(
-        ctx: Context<ParitySwap>,
+    /// Create the router config. Only the program's upgrade authority may do
+    /// this, and it becomes the admin of the DEX allowlist.
+    pub fn initialize_config(ctx: Context<InitializeConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.allowed_programs = Vec::new();
+        config.weighted_target = 0;
+        config.cooldown_slots = 0;
+        config.rate_window_slots = 0;
+        config.max_window_volume = 0;
+        config.volume_mint = Pubkey::default();
+        config.commit_deposit = 0;
+        config.bump = ctx.bumps.config;
+        Ok(())
+    }
+
+    /// Allow the router to forward swaps to `program_id`.
+    pub fn add_allowed_program(ctx: Context<UpdateConfig>, program_id: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(
+            !config.allowed_programs.contains(&program_id),
+            ErrorCode::ProgramAlreadyAllowed
+        );
+        require!(
+            config.allowed_programs.len() < MAX_ALLOWED_PROGRAMS,
+            ErrorCode::AllowlistFull
+        );
+        config.allowed_programs.push(program_id);
+        Ok(())
+    }
+
+    /// Stop forwarding swaps to `program_id`.
+    pub fn remove_allowed_program(ctx: Context<UpdateConfig>, program_id: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let index = config
+            .allowed_programs
+            .iter()
+            .position(|p| *p == program_id)
+            .ok_or(ErrorCode::ProgramNotInAllowlist)?;
+        config.allowed_programs.swap_remove(index);
+        Ok(())
+    }
+
+    /// Set the target the `WeightedTarget` strategy compares against.
+    pub fn set_weighted_target(ctx: Context<UpdateConfig>, target: i64) -> Result<()> {
+        ctx.accounts.config.weighted_target = target;
+        Ok(())
+    }
+
+    /// Throttle swaps per authority: at least `cooldown_slots` between two
+    /// swaps, and at most `max_window_volume` of `volume_mint` spent per
+    /// `rate_window_slots`. A `max_window_volume` of 0 disables the cap; a
+    /// non-zero cap needs a window of at least one slot.
+    pub fn set_rate_limits(
+        ctx: Context<UpdateConfig>,
+        cooldown_slots: u64,
+        rate_window_slots: u64,
+        max_window_volume: u64,
+        volume_mint: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.cooldown_slots = cooldown_slots;
+        config.rate_window_slots = rate_window_slots;
+        config.max_window_volume = max_window_volume;
+        config.volume_mint = volume_mint;
+        config.check_rate_limits()?;
+
+        msg!(
+            "Rate limits: cooldown {} slots, {} of {} per {} slots",
+            cooldown_slots,
+            max_window_volume,
+            volume_mint,
+            rate_window_slots
+        );
+        Ok(())
+    }
+
+    /// Set the lamports `commit_randomness` locks until the reveal. A trader
+    /// who withholds the reveal forfeits them to the admin.
+    pub fn set_commit_deposit(ctx: Context<UpdateConfig>, lamports: u64) -> Result<()> {
+        ctx.accounts.config.commit_deposit = lamports;
+        msg!("Commit deposit: {} lamports", lamports);
+        Ok(())
+    }
+
+    /// Pick one of the candidate `routes` from the inputs using `strategy`,
+    /// then forward that route's swap instruction (e.g., Raydium swap) via CPI.
+    ///
+    /// `remaining_accounts` holds every route's accounts back to back, each
+    /// route consuming `accounts_len` of them. Binary strategies expect two
+    /// routes ordered (sell, buy); `Modulo` takes any number of routes.
+    ///
+    /// The chosen route's destination token account must grow by at least its
+    /// minimum output, otherwise the whole swap is rolled back.
+    pub fn execute_parity_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ParitySwap<'info>>,
         numbers: Vec<i64>,
-        buy_ix_data: Vec<u8>,
-        sell_ix_data: Vec<u8>,
-        buy_accounts_len: u8,
+        strategy: Strategy,
+        routes: Vec<SwapRoute>,
     ) -> Result<()> {
-        require!(!numbers.is_empty(), ErrorCode::NoInputs);
-        require!(numbers.len() <= MAX_INPUTS, ErrorCode::TooManyInputs);
+        let signer = RouteSigner::Wallet(ctx.accounts.authority.key());
+        let (route_index, sum, range) = plan_route(
+            &ctx.accounts.config,
+            &signer,
+            ctx.remaining_accounts,
+            &numbers,
+            &strategy,
+            &routes,
+        )?;
+        let route = &routes[route_index];
+        let accounts = &ctx.remaining_accounts[range];
 
-        let sum: i128 = numbers.iter().map(|n| *n as i128).sum();
-        let sum_i64 = i64::try_from(sum).map_err(|_| ErrorCode::SumOverflow)?;
-        let is_buy = sum & 1 != 0;
+        let rate_limit = &mut ctx.accounts.rate_limit;
+        throttle(rate_limit, ctx.accounts.authority.key(), ctx.bumps.rate_limit, &ctx.accounts.config)?;
+        let fill = execute_route(
+            ctx.accounts.dex_program.key(),
+            accounts,
+            route,
+            &signer,
+            rate_limit,
+            &ctx.accounts.config,
+        )?;
 
-        let buy_len = buy_accounts_len as usize;
-        let total_len = ctx.remaining_accounts.len();
-        require!(buy_len <= total_len, ErrorCode::InvalidAccountSplit);
+        emit!(SwapDecision {
+            is_buy: routes.len() == 2 && route_index == 1,
+            sum,
+            selected_accounts: accounts.len() as u8,
+            strategy: strategy.clone(),
+            route_index: route_index as u8,
+        });
+        emit!(SwapDecisionV2 {
+            version: SWAP_DECISION_VERSION,
+            authority: ctx.accounts.authority.key(),
+            dex_program: ctx.accounts.dex_program.key(),
+            strategy: Some(strategy),
+            input_count: numbers.len() as u8,
+            sum,
+            route_index: route_index as u8,
+            route_count: routes.len() as u8,
+            selected_accounts: accounts.len() as u8,
+            fill,
+        });
+
+        Ok(())
+    }
+
+    /// Dry run of `execute_parity_swap`: validates the inputs and account
+    /// split exactly the same way and returns the decision (also set as
+    /// return data) without invoking the DEX.
+    pub fn preview_parity_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PreviewParitySwap<'info>>,
+        numbers: Vec<i64>,
+        strategy: Strategy,
+        routes: Vec<SwapRoute>,
+    ) -> Result<SwapPreview> {
+        let signer = RouteSigner::Wallet(ctx.accounts.authority.key());
+        let (route_index, sum, range) = plan_route(
+            &ctx.accounts.config,
+            &signer,
+            ctx.remaining_accounts,
+            &numbers,
+            &strategy,
+            &routes,
+        )?;
+        let route = &routes[route_index];
+        let accounts = &ctx.remaining_accounts[range];
+
+        let destination_info = &accounts[route.destination_index as usize];
+        let destination = InterfaceAccount::<TokenAccount>::try_from(destination_info)?;
+
+        Ok(SwapPreview {
+            is_buy: routes.len() == 2 && route_index == 1,
+            sum,
+            route_index: route_index as u8,
+            selected_accounts: accounts.len() as u8,
+            destination: destination.key(),
+            destination_balance: destination.amount,
+        })
+    }
+
+    /// Commit to a randomness-driven swap: `swap_commitment(secret, routes,
+    /// route accounts)`. The reveal mixes the secret with the hash of this
+    /// slot, which nobody knows yet, and must use the committed routes.
+    ///
+    /// The config's `commit_deposit` moves into the commitment account. The
+    /// reveal returns it; `cancel_commitment` forfeits it, so withholding an
+    /// unfavourable reveal costs the deposit.
+    pub fn commit_randomness(ctx: Context<CommitRandomness>, commitment: [u8; 32]) -> Result<()> {
+        let deposit = ctx.accounts.config.commit_deposit;
+        if deposit > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: ctx.accounts.commitment.to_account_info(),
+                    },
+                ),
+                deposit,
+            )?;
+        }
+
+        let record = &mut ctx.accounts.commitment;
+        record.authority = ctx.accounts.authority.key();
+        record.commitment = commitment;
+        record.slot = Clock::get()?.slot;
+        record.deposit = deposit;
+        record.bump = ctx.bumps.commitment;
 
-        let (ix_data, accounts) = if is_buy {
-            (buy_ix_data, &ctx.remaining_accounts[..buy_len])
+        msg!(
+            "Randomness committed at slot {} with a {} lamport deposit",
+            record.slot,
+            deposit
+        );
+        Ok(())
+    }
+
+    /// Reveal `secret` and swap along the route picked by
+    /// `sha256(secret || slot_hash(commit slot)) mod routes.len()`. Routes and
+    /// accounts work as in `execute_parity_swap` and must be the committed
+    /// ones. The commitment is closed and the inputs are emitted so anyone can
+    /// recompute the decision, and the deposit is returned with its rent.
+    pub fn execute_committed_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteCommittedSwap<'info>>,
+        secret: [u8; 32],
+        routes: Vec<SwapRoute>,
+    ) -> Result<()> {
+        let commitment = &ctx.accounts.commitment;
+        let routes_hash = routes_hash(&routes)?;
+        let accounts_hash = accounts_hash(ctx.remaining_accounts.iter().map(|acc| acc.key));
+        require!(
+            swap_commitment(&secret, &routes_hash, &accounts_hash) == commitment.commitment,
+            ErrorCode::CommitmentMismatch
+        );
+        require!(
+            Clock::get()?.slot > commitment.slot,
+            ErrorCode::RevealTooEarly
+        );
+
+        let slot_hash = {
+            let data = ctx.accounts.slot_hashes.try_borrow_data()?;
+            find_slot_hash(&data, commitment.slot).ok_or(ErrorCode::CommitmentExpired)?
+        };
+        let randomness = committed_randomness(&secret, &slot_hash);
+
+        validate_routes(&routes, ctx.remaining_accounts.len())?;
+        let route_index = committed_route(&randomness, routes.len());
+        let signer = RouteSigner::Wallet(ctx.accounts.authority.key());
+        let range = route_range(
+            &signer,
+            ctx.remaining_accounts,
+            &routes,
+            route_index,
+        )?;
+        let accounts = &ctx.remaining_accounts[range];
+
+        let rate_limit = &mut ctx.accounts.rate_limit;
+        throttle(
+            rate_limit,
+            ctx.accounts.authority.key(),
+            ctx.bumps.rate_limit,
+            &ctx.accounts.config,
+        )?;
+        let fill = execute_route(
+            ctx.accounts.dex_program.key(),
+            accounts,
+            &routes[route_index],
+            &signer,
+            rate_limit,
+            &ctx.accounts.config,
+        )?;
+
+        emit!(CommittedSwapDecision {
+            authority: commitment.authority,
+            commit_slot: commitment.slot,
+            secret,
+            slot_hash,
+            randomness,
+            route_index: route_index as u8,
+            route_count: routes.len() as u8,
+            routes_hash,
+            accounts_hash,
+        });
+        emit!(SwapDecisionV2 {
+            version: SWAP_DECISION_VERSION,
+            authority: ctx.accounts.authority.key(),
+            dex_program: ctx.accounts.dex_program.key(),
+            strategy: None,
+            input_count: 0,
+            sum: 0,
+            route_index: route_index as u8,
+            route_count: routes.len() as u8,
+            selected_accounts: accounts.len() as u8,
+            fill,
+        });
+
+        Ok(())
+    }
+
+    /// Close a commitment whose slot hash has left the `SlotHashes` sysvar
+    /// and can no longer be revealed. Its deposit goes to the config admin
+    /// and the rent back to the authority.
+    pub fn cancel_commitment(ctx: Context<CancelCommitment>) -> Result<()> {
+        let commitment = &ctx.accounts.commitment;
+        let expiry = commitment
+            .slot
+            .checked_add(SLOT_HASHES_MAX_ENTRIES)
+            .ok_or(ErrorCode::SumOverflow)?;
+        require!(
+            Clock::get()?.slot > expiry,
+            ErrorCode::CommitmentNotExpired
+        );
+
+        let deposit = commitment.deposit;
+        commitment.sub_lamports(deposit)?;
+        ctx.accounts.admin.add_lamports(deposit)?;
+
+        emit!(CommitmentForfeited {
+            authority: commitment.authority,
+            commit_slot: commitment.slot,
+            commitment: commitment.commitment,
+            deposit,
+            admin: ctx.accounts.admin.key(),
+        });
+        Ok(())
+    }
+
+    /// Create a router-owned vault for the signer. Token accounts whose owner
+    /// is the vault PDA hold its funds; `operator` may trade them through
+    /// `execute_vault_swap` but never withdraw.
+    pub fn create_vault(ctx: Context<CreateVault>, vault_id: u64, operator: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.owner = ctx.accounts.owner.key();
+        vault.operator = operator;
+        vault.vault_id = vault_id;
+        vault.limits = Vec::new();
+        vault.bump = ctx.bumps.vault;
+
+        msg!("Vault {} created for {}", vault_id, vault.owner);
+        Ok(())
+    }
+
+    /// Replace the vault operator; `Pubkey::default()` leaves only the owner.
+    pub fn set_vault_operator(ctx: Context<UpdateVault>, operator: Pubkey) -> Result<()> {
+        ctx.accounts.vault.operator = operator;
+        Ok(())
+    }
+
+    /// Let the vault swap `input_mint` into `output_mint`, spending at most
+    /// `max_amount_in` per swap for at least `min_price` (output per input,
+    /// scaled by `PRICE_SCALE`). A `max_amount_in` of 0 removes the pair.
+    /// Vault swaps between mints without a limit fail.
+    pub fn set_vault_limit(
+        ctx: Context<UpdateVault>,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        max_amount_in: u64,
+        min_price: u64,
+    ) -> Result<()> {
+        let limits = &mut ctx.accounts.vault.limits;
+        limits.retain(|limit| limit.input_mint != input_mint || limit.output_mint != output_mint);
+        if max_amount_in > 0 {
+            require!(limits.len() < MAX_VAULT_LIMITS, ErrorCode::VaultLimitsFull);
+            limits.push(VaultLimit {
+                input_mint,
+                output_mint,
+                max_amount_in,
+                min_price,
+            });
+        }
+
+        msg!(
+            "Vault limit {} -> {}: at most {} in, at least {} out per {} in",
+            input_mint,
+            output_mint,
+            max_amount_in,
+            min_price,
+            PRICE_SCALE
+        );
+        Ok(())
+    }
+
+    /// Move `amount` out of a vault token account. Owner only.
+    pub fn withdraw_from_vault(ctx: Context<WithdrawFromVault>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        let vault = &ctx.accounts.vault;
+        let owner = vault.owner;
+        let vault_id = vault.vault_id.to_le_bytes();
+        let seeds = &[b"vault".as_ref(), owner.as_ref(), vault_id.as_ref(), &[vault.bump]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+        )?;
+
+        msg!("Withdrew {} from vault {}", amount, vault.vault_id);
+        Ok(())
+    }
+
+    /// `execute_parity_swap` with funds held by a vault: the vault PDA is the
+    /// forwarded authority and the router signs for it, so the caller (owner
+    /// or operator) never passes its own signature to the DEX.
+    pub fn execute_vault_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteVaultSwap<'info>>,
+        numbers: Vec<i64>,
+        strategy: Strategy,
+        routes: Vec<SwapRoute>,
+    ) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let owner = vault.owner;
+        let vault_id = vault.vault_id.to_le_bytes();
+        let bump = [vault.bump];
+        let seeds: &[&[u8]] = &[b"vault", owner.as_ref(), vault_id.as_ref(), &bump];
+        let signer = RouteSigner::Vault {
+            key: vault.key(),
+            seeds,
+            limits: &vault.limits,
+        };
+
+        let (route_index, sum, range) = plan_route(
+            &ctx.accounts.config,
+            &signer,
+            ctx.remaining_accounts,
+            &numbers,
+            &strategy,
+            &routes,
+        )?;
+        let route = &routes[route_index];
+        let accounts = &ctx.remaining_accounts[range];
+
+        let rate_limit = &mut ctx.accounts.rate_limit;
+        throttle(rate_limit, ctx.accounts.vault.key(), ctx.bumps.rate_limit, &ctx.accounts.config)?;
+        let fill = execute_route(
+            ctx.accounts.dex_program.key(),
+            accounts,
+            route,
+            &signer,
+            rate_limit,
+            &ctx.accounts.config,
+        )?;
+
+        emit!(SwapDecision {
+            is_buy: routes.len() == 2 && route_index == 1,
+            sum,
+            selected_accounts: accounts.len() as u8,
+            strategy: strategy.clone(),
+            route_index: route_index as u8,
+        });
+        emit!(SwapDecisionV2 {
+            version: SWAP_DECISION_VERSION,
+            authority: ctx.accounts.authority.key(),
+            dex_program: ctx.accounts.dex_program.key(),
+            strategy: Some(strategy),
+            input_count: numbers.len() as u8,
+            sum,
+            route_index: route_index as u8,
+            route_count: routes.len() as u8,
+            selected_accounts: accounts.len() as u8,
+            fill,
+        });
+
+        Ok(())
+    }
+
+    /// Parity swap against a Raydium AMM v4 pool. The router builds the
+    /// `swap_base_in` instruction itself: buy spends the user's PC (quote)
+    /// account for coin (base), sell does the reverse.
+    pub fn parity_swap_raydium_amm(
+        ctx: Context<ParitySwapRaydiumAmm>,
+        numbers: Vec<i64>,
+        strategy: Strategy,
+        buy: SwapAmounts,
+        sell: SwapAmounts,
+    ) -> Result<()> {
+        let (is_buy, sum) = buy_or_sell(&strategy, &numbers, &ctx.accounts.config)?;
+
+        let accounts = ctx.accounts;
+        throttle(
+            &mut accounts.rate_limit,
+            accounts.authority.key(),
+            ctx.bumps.rate_limit,
+            &accounts.config,
+        )?;
+        let (amounts, source_mint) = if is_buy {
+            (buy, accounts.user_pc_account.mint)
+        } else {
+            (sell, accounts.user_coin_account.mint)
+        };
+        accounts
+            .rate_limit
+            .spend(&accounts.config, &source_mint, amounts.amount_in)?;
+        let (source, destination) = if is_buy {
+            (accounts.user_pc_account.to_account_info(), &mut accounts.user_coin_account)
         } else {
-            (sell_ix_data, &ctx.remaining_accounts[buy_len..])
+            (accounts.user_coin_account.to_account_info(), &mut accounts.user_pc_account)
         };
 
-        require!(!accounts.is_empty(), ErrorCode::MissingCpiAccounts);
+        let pool = AmmV4Pool {
+            token_program: accounts.token_program.key(),
+            amm: accounts.amm.key(),
+            amm_authority: accounts.amm_authority.key(),
+            amm_open_orders: accounts.amm_open_orders.key(),
+            amm_target_orders: accounts.amm_target_orders.key(),
+            pool_coin_vault: accounts.pool_coin_vault.key(),
+            pool_pc_vault: accounts.pool_pc_vault.key(),
+            market_program: accounts.market_program.key(),
+            market: accounts.market.key(),
+            market_bids: accounts.market_bids.key(),
+            market_asks: accounts.market_asks.key(),
+            market_event_queue: accounts.market_event_queue.key(),
+            market_coin_vault: accounts.market_coin_vault.key(),
+            market_pc_vault: accounts.market_pc_vault.key(),
+            market_vault_signer: accounts.market_vault_signer.key(),
+        };
+        let ix = raydium::amm_v4_swap_base_in(
+            accounts.amm_program.key(),
+            &pool,
+            source.key(),
+            destination.key(),
+            accounts.authority.key(),
+            amounts.amount_in,
+            amounts.min_amount_out,
+        );
+
+        let account_infos = [
+            accounts.token_program.to_account_info(),
+            accounts.amm.to_account_info(),
+            accounts.amm_authority.to_account_info(),
+            accounts.amm_open_orders.to_account_info(),
+            accounts.amm_target_orders.to_account_info(),
+            accounts.pool_coin_vault.to_account_info(),
+            accounts.pool_pc_vault.to_account_info(),
+            accounts.market_program.to_account_info(),
+            accounts.market.to_account_info(),
+            accounts.market_bids.to_account_info(),
+            accounts.market_asks.to_account_info(),
+            accounts.market_event_queue.to_account_info(),
+            accounts.market_coin_vault.to_account_info(),
+            accounts.market_pc_vault.to_account_info(),
+            accounts.market_vault_signer.to_account_info(),
+            source,
+            destination.to_account_info(),
+            accounts.authority.to_account_info(),
+        ];
+
+        let fill =
+            invoke_with_min_out(&ix, &account_infos, destination, amounts.min_amount_out, &[])?;
+
+        emit!(SwapDecision {
+            is_buy,
+            sum,
+            selected_accounts: raydium::AMM_V4_SWAP_ACCOUNTS_LEN as u8,
+            strategy: strategy.clone(),
+            route_index: is_buy as u8,
+        });
+        emit!(SwapDecisionV2 {
+            version: SWAP_DECISION_VERSION,
+            authority: accounts.authority.key(),
+            dex_program: accounts.amm_program.key(),
+            strategy: Some(strategy),
+            input_count: numbers.len() as u8,
+            sum,
+            route_index: is_buy as u8,
+            route_count: 2,
+            selected_accounts: raydium::AMM_V4_SWAP_ACCOUNTS_LEN as u8,
+            fill,
+        });
+
+        Ok(())
+    }
+
+    /// Parity swap against a Raydium CLMM pool. Buy swaps quote for base, sell
+    /// swaps base for quote, both as exact-input swaps.
+    ///
+    /// `remaining_accounts` holds the tick arrays each direction crosses: the
+    /// first `buy_tick_arrays_len` for buy, the rest for sell.
+    pub fn parity_swap_raydium_clmm<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ParitySwapRaydiumClmm<'info>>,
+        numbers: Vec<i64>,
+        strategy: Strategy,
+        buy: ClmmSwapAmounts,
+        sell: ClmmSwapAmounts,
+        buy_tick_arrays_len: u8,
+    ) -> Result<()> {
+        let (is_buy, sum) = buy_or_sell(&strategy, &numbers, &ctx.accounts.config)?;
 
-        // Ensure the caller who signs this transaction is also forwarded to the CPI.
-        let authority_key = ctx.accounts.authority.key();
+        let buy_len = buy_tick_arrays_len as usize;
         require!(
-            accounts
-                .iter()
-                .any(|acc| *acc.key == authority_key && acc.is_signer),
-            ErrorCode::AuthorityNotForwarded
+            buy_len <= ctx.remaining_accounts.len(),
+            ErrorCode::InvalidAccountSplit
         );
+        let tick_arrays = if is_buy {
+            &ctx.remaining_accounts[..buy_len]
+        } else {
+            &ctx.remaining_accounts[buy_len..]
+        };
+        require!(!tick_arrays.is_empty(), ErrorCode::MissingCpiAccounts);
+
+        let accounts = ctx.accounts;
+        throttle(
+            &mut accounts.rate_limit,
+            accounts.authority.key(),
+            ctx.bumps.rate_limit,
+            &accounts.config,
+        )?;
+        let (amounts, source_mint) = if is_buy {
+            (buy, accounts.user_quote_account.mint)
+        } else {
+            (sell, accounts.user_base_account.mint)
+        };
+        accounts
+            .rate_limit
+            .spend(&accounts.config, &source_mint, amounts.amount_in)?;
+        let (source, input_vault, output_vault, destination) = if is_buy {
+            (
+                accounts.user_quote_account.to_account_info(),
+                accounts.quote_vault.to_account_info(),
+                accounts.base_vault.to_account_info(),
+                &mut accounts.user_base_account,
+            )
+        } else {
+            (
+                accounts.user_base_account.to_account_info(),
+                accounts.base_vault.to_account_info(),
+                accounts.quote_vault.to_account_info(),
+                &mut accounts.user_quote_account,
+            )
+        };
 
-        let ix = Instruction {
-            program_id: ctx.accounts.dex_program.key(),
-            accounts: accounts
-                .iter()
-                .map(|acc| {
-                    if acc.is_writable {
-                        AccountMeta::new(*acc.key, acc.is_signer)
-                    } else {
-                        AccountMeta::new_readonly(*acc.key, acc.is_signer)
-                    }
-                })
-                .collect(),
-            data: ix_data,
+        let keys = ClmmSwapAccounts {
+            payer: accounts.authority.key(),
+            amm_config: accounts.amm_config.key(),
+            pool_state: accounts.pool_state.key(),
+            input_token_account: source.key(),
+            output_token_account: destination.key(),
+            input_vault: input_vault.key(),
+            output_vault: output_vault.key(),
+            observation_state: accounts.observation_state.key(),
+            token_program: accounts.token_program.key(),
         };
+        let tick_keys: Vec<Pubkey> = tick_arrays.iter().map(|acc| *acc.key).collect();
+        let ix = raydium::clmm_swap(
+            accounts.clmm_program.key(),
+            &keys,
+            &tick_keys,
+            amounts.amount_in,
+            amounts.min_amount_out,
+            amounts.sqrt_price_limit_x64,
+            true,
+        );
+
+        let mut account_infos = vec![
+            accounts.authority.to_account_info(),
+            accounts.amm_config.to_account_info(),
+            accounts.pool_state.to_account_info(),
+            source,
+            destination.to_account_info(),
+            input_vault,
+            output_vault,
+            accounts.observation_state.to_account_info(),
+            accounts.token_program.to_account_info(),
+        ];
+        account_infos.extend(tick_arrays.iter().cloned());
 
-        invoke(&ix, accounts)?;
+        let fill =
+            invoke_with_min_out(&ix, &account_infos, destination, amounts.min_amount_out, &[])?;
 
         emit!(SwapDecision {
             is_buy,
-            sum: sum_i64,
-            selected_accounts: accounts.len() as u8,
+            sum,
+            selected_accounts: account_infos.len() as u8,
+            strategy: strategy.clone(),
+            route_index: is_buy as u8,
+        });
+        emit!(SwapDecisionV2 {
+            version: SWAP_DECISION_VERSION,
+            authority: accounts.authority.key(),
+            dex_program: accounts.clmm_program.key(),
+            strategy: Some(strategy),
+            input_count: numbers.len() as u8,
+            sum,
+            route_index: is_buy as u8,
+            route_count: 2,
+            selected_accounts: account_infos.len() as u8,
+            fill,
         });
 
         Ok(())
     }
 }
 
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Validate `routes` against `remaining_accounts` and pick one with
+/// `strategy`. Returns the route index, the decision value and the range of
+/// `remaining_accounts` the route consumes.
+fn plan_route(
+    config: &RouterConfig,
+    signer: &RouteSigner,
+    remaining_accounts: &[AccountInfo],
+    numbers: &[i64],
+    strategy: &Strategy,
+    routes: &[SwapRoute],
+) -> Result<(usize, i64, Range<usize>)> {
+    validate_routes(routes, remaining_accounts.len())?;
+    let (route_index, sum) = strategy.select_route(numbers, config.weighted_target, routes.len())?;
+    let range = route_range(signer, remaining_accounts, routes, route_index)?;
+    Ok((route_index, sum, range))
+}
+
+/// Check the route count and that the routes consume exactly the
+/// `remaining_accounts` provided.
+fn validate_routes(routes: &[SwapRoute], remaining_len: usize) -> Result<()> {
+    require!(!routes.is_empty(), ErrorCode::MissingCpiAccounts);
+    require!(routes.len() <= MAX_ROUTES, ErrorCode::TooManyRoutes);
+
+    let total_len: usize = routes.iter().map(|r| r.accounts_len as usize).sum();
+    require!(total_len == remaining_len, ErrorCode::InvalidAccountSplit);
+    Ok(())
+}
+
+/// Who signs a forwarded route.
+enum RouteSigner<'a> {
+    /// The transaction signer's own wallet, forwarded with its signature.
+    Wallet(Pubkey),
+    /// A router `Vault` PDA, signed for with `invoke_signed` and bound by
+    /// its owner's `limits`.
+    Vault {
+        key: Pubkey,
+        seeds: &'a [&'a [u8]],
+        limits: &'a [VaultLimit],
+    },
+}
+
+impl RouteSigner<'_> {
+    /// Owner of the token accounts the route spends from.
+    fn key(&self) -> Pubkey {
+        match self {
+            RouteSigner::Wallet(key) | RouteSigner::Vault { key, .. } => *key,
+        }
+    }
+}
+
+/// Range of `remaining_accounts` used by route `route_index`, after checking
+/// the authority is forwarded and the destination index is in range.
+fn route_range(
+    signer: &RouteSigner,
+    remaining_accounts: &[AccountInfo],
+    routes: &[SwapRoute],
+    route_index: usize,
+) -> Result<Range<usize>> {
+    let start: usize = routes[..route_index]
+        .iter()
+        .map(|r| r.accounts_len as usize)
+        .sum();
+    let route = &routes[route_index];
+    let range = start..start + route.accounts_len as usize;
+    let accounts = &remaining_accounts[range.clone()];
+
+    require!(!accounts.is_empty(), ErrorCode::MissingCpiAccounts);
+
+    // Ensure the signing authority is forwarded to the CPI.
+    let forwarded = match signer {
+        RouteSigner::Wallet(authority) => accounts
+            .iter()
+            .any(|acc| acc.key == authority && acc.is_signer),
+        RouteSigner::Vault { key, .. } => accounts.iter().any(|acc| acc.key == key),
+    };
+    require!(forwarded, ErrorCode::AuthorityNotForwarded);
+    require!(
+        (route.destination_index as usize) < accounts.len(),
+        ErrorCode::InvalidDestination
+    );
+
+    Ok(range)
+}
+
+/// Forward `route` to `dex_program` with `accounts` and enforce its minimum output.
+/// What the signer spent of `config.volume_mint` counts against `rate_limit`.
+///
+/// For a vault, only the vault PDA is passed on as signer, the route must pay
+/// a non-zero minimum into a token account the vault owns, no other account
+/// may be credited more than the vault paid (`check_vault_flow`), and the
+/// trade must stay within the owner's limits (`check_vault_limits`).
+fn execute_route<'info>(
+    dex_program: Pubkey,
+    accounts: &'info [AccountInfo<'info>],
+    route: &SwapRoute,
+    signer: &RouteSigner,
+    rate_limit: &mut RateLimit,
+    config: &RouterConfig,
+) -> Result<SwapFill> {
+    let destination_info = &accounts[route.destination_index as usize];
+    let mut destination = InterfaceAccount::<TokenAccount>::try_from(destination_info)?;
+
+    let signer_seeds: &[&[&[u8]]] = match signer {
+        RouteSigner::Wallet(_) => &[],
+        RouteSigner::Vault { key, seeds, .. } => {
+            require_keys_eq!(destination.owner, *key, ErrorCode::DestinationNotVault);
+            require!(route.min_amount_out > 0, ErrorCode::InvalidAmount);
+            std::slice::from_ref(seeds)
+        }
+    };
+
+    let ix = Instruction {
+        program_id: dex_program,
+        accounts: route_metas(accounts, signer),
+        data: route.ix_data.clone(),
+    };
+
+    let before = token_balances(accounts)?;
+    let fill = invoke_with_min_out(&ix, accounts, &mut destination, route.min_amount_out, signer_seeds)?;
+    let after = token_balances(accounts)?;
+
+    if let RouteSigner::Vault { key, limits, .. } = signer {
+        check_vault_flow(key, &before, &after)?;
+        check_vault_limits(limits, key, &destination.mint, fill.amount_out(), &before, &after)?;
+    }
+    // The route's input is only known once the DEX has debited it.
+    let amount_in = spent(&signer.key(), &config.volume_mint, &before, &after);
+    rate_limit.spend(config, &config.volume_mint, amount_in)?;
+    Ok(fill)
+}
+
+/// Balances of the writable token accounts in `accounts`, each key once.
+fn token_balances(accounts: &[AccountInfo]) -> Result<Vec<TokenBalance>> {
+    let mut balances: Vec<TokenBalance> = Vec::new();
+    for acc in accounts
+        .iter()
+        .filter(|acc| acc.is_writable && TokenAccount::owners().contains(acc.owner))
+    {
+        if balances.iter().any(|balance| balance.key == *acc.key) {
+            continue;
+        }
+        let token = TokenAccount::try_deserialize(&mut &acc.try_borrow_data()?[..])?;
+        balances.push(TokenBalance {
+            key: *acc.key,
+            mint: token.mint,
+            owner: token.owner,
+            amount: token.amount,
+        });
+    }
+    Ok(balances)
+}
+
+/// Net amount of `mint` that accounts owned by `owner` lost between `before`
+/// and `after`.
+fn spent(owner: &Pubkey, mint: &Pubkey, before: &[TokenBalance], after: &[TokenBalance]) -> u64 {
+    let total = |balances: &[TokenBalance]| -> u128 {
+        balances
+            .iter()
+            .filter(|balance| balance.owner == *owner && balance.mint == *mint)
+            .map(|balance| u128::from(balance.amount))
+            .sum()
+    };
+    u64::try_from(total(before).saturating_sub(total(after))).unwrap_or(u64::MAX)
+}
+
+/// Check where a vault route moved tokens. Accounts the vault does not own
+/// (pool reserves) may only be credited in a mint the vault paid with, and in
+/// total no more than it paid, so the output cannot be diverted past the vault.
+fn check_vault_flow(vault: &Pubkey, before: &[TokenBalance], after: &[TokenBalance]) -> Result<()> {
+    fn add(totals: &mut Vec<(Pubkey, u64)>, mint: Pubkey, amount: u64) -> Result<()> {
+        match totals.iter_mut().find(|(m, _)| *m == mint) {
+            Some((_, total)) => *total = total.checked_add(amount).ok_or(ErrorCode::SumOverflow)?,
+            None => totals.push((mint, amount)),
+        }
+        Ok(())
+    }
+
+    require!(before.len() == after.len(), ErrorCode::OutputNotVault);
+    let (mut paid, mut credited) = (Vec::new(), Vec::new());
+    for (before, after) in before.iter().zip(after) {
+        require!(
+            before.key == after.key && before.mint == after.mint && before.owner == after.owner,
+            ErrorCode::OutputNotVault
+        );
+        if before.owner == *vault {
+            add(&mut paid, before.mint, before.amount.saturating_sub(after.amount))?;
+        } else {
+            add(&mut credited, before.mint, after.amount.saturating_sub(before.amount))?;
+        }
+    }
+
+    for (mint, amount) in credited {
+        let paid = paid.iter().find(|(m, _)| *m == mint).map_or(0, |(_, paid)| *paid);
+        require!(amount <= paid, ErrorCode::OutputNotVault);
+    }
+    Ok(())
+}
+
+/// Check a vault trade against its owner's limits: it may spend a single
+/// mint, which needs a limit towards `output_mint`, no more than the limit's
+/// `max_amount_in`, and must get `amount_out` of at least `min_price` per unit.
+fn check_vault_limits(
+    limits: &[VaultLimit],
+    vault: &Pubkey,
+    output_mint: &Pubkey,
+    amount_out: u64,
+    before: &[TokenBalance],
+    after: &[TokenBalance],
+) -> Result<()> {
+    let mut paid: Vec<(Pubkey, u64)> = Vec::new();
+    for balance in before.iter().filter(|b| b.owner == *vault && b.mint != *output_mint) {
+        if paid.iter().any(|(mint, _)| *mint == balance.mint) {
+            continue;
+        }
+        let amount = spent(vault, &balance.mint, before, after);
+        if amount > 0 {
+            paid.push((balance.mint, amount));
+        }
+    }
+
+    let (input_mint, amount_in) = match paid[..] {
+        [] => return Ok(()),
+        [paid] => paid,
+        _ => return err!(ErrorCode::VaultPairNotAllowed),
+    };
+    let limit = limits
+        .iter()
+        .find(|limit| limit.input_mint == input_mint && limit.output_mint == *output_mint)
+        .ok_or(ErrorCode::VaultPairNotAllowed)?;
+    require!(amount_in <= limit.max_amount_in, ErrorCode::VaultAmountInExceeded);
+    require!(
+        u128::from(amount_out) * u128::from(PRICE_SCALE) >= u128::from(amount_in) * u128::from(limit.min_price),
+        ErrorCode::VaultPriceTooLow
+    );
+    Ok(())
+}
+
+/// Account metas forwarded to the DEX. Writability is passed through as is,
+/// but only `signer`'s key is marked as signer: other transaction signers'
+/// signatures never reach the DEX.
+fn route_metas(accounts: &[AccountInfo], signer: &RouteSigner) -> Vec<AccountMeta> {
+    let is_signer = |acc: &AccountInfo| match signer {
+        RouteSigner::Wallet(authority) => acc.key == authority && acc.is_signer,
+        RouteSigner::Vault { key, .. } => acc.key == key,
+    };
+    accounts
+        .iter()
+        .map(|acc| {
+            if acc.is_writable {
+                AccountMeta::new(*acc.key, is_signer(acc))
+            } else {
+                AccountMeta::new_readonly(*acc.key, is_signer(acc))
+            }
+        })
+        .collect()
+}
+
+/// `route_metas` for the fuzz target, which checks the forwarded signers
+/// against the flags the runtime gives a route's accounts.
+#[cfg(feature = "fuzz")]
+pub mod fuzz {
+    use super::*;
+
+    /// Metas `execute_parity_swap` forwards for a route of `authority`.
+    pub fn wallet_route_metas(accounts: &[AccountInfo], authority: Pubkey) -> Vec<AccountMeta> {
+        route_metas(accounts, &RouteSigner::Wallet(authority))
+    }
+}
+
+/// Look up the hash of `slot` in raw `SlotHashes` sysvar data
+/// (`u64` length, then `(slot, hash)` entries, newest first).
+fn find_slot_hash(data: &[u8], slot: u64) -> Option<[u8; 32]> {
+    let len = u64::from_le_bytes(data.get(..8)?.try_into().ok()?) as usize;
+    data.get(8..)?
+        .chunks_exact(40)
+        .take(len)
+        .find(|entry| entry[..8] == slot.to_le_bytes())
+        .map(|entry| entry[8..].try_into().unwrap())
+}
+
+/// What `commit_randomness` commits to:
+/// `sha256(secret || routes_hash || accounts_hash)`, so the reveal cannot
+/// pick its routes after the slot hash is known.
+pub fn swap_commitment(
+    secret: &[u8; 32],
+    routes_hash: &[u8; 32],
+    accounts_hash: &[u8; 32],
+) -> [u8; 32] {
+    hashv(&[secret, routes_hash, accounts_hash]).to_bytes()
+}
+
+/// `sha256` of the borsh-encoded `routes` argument (`u32` length, then each
+/// `SwapRoute`).
+pub fn routes_hash(routes: &[SwapRoute]) -> Result<[u8; 32]> {
+    let mut data = Vec::new();
+    routes.serialize(&mut data)?;
+    Ok(hashv(&[&data]).to_bytes())
+}
+
+/// `sha256` of the route account keys (the remaining accounts), in order.
+pub fn accounts_hash<'a>(keys: impl IntoIterator<Item = &'a Pubkey>) -> [u8; 32] {
+    let keys: Vec<&[u8]> = keys.into_iter().map(|key| key.as_ref()).collect();
+    hashv(&keys).to_bytes()
+}
+
+/// Randomness of a revealed commitment: `sha256(secret || slot_hash)`.
+/// Neither party knows the slot hash when the commitment is made.
+pub fn committed_randomness(secret: &[u8; 32], slot_hash: &[u8; 32]) -> [u8; 32] {
+    hashv(&[secret, slot_hash]).to_bytes()
+}
+
+/// Route picked by committed randomness: `randomness mod route_count`, read as
+/// a little-endian `u64`. With two (sell, buy) routes this is its parity bit.
+pub fn committed_route(randomness: &[u8; 32], route_count: usize) -> usize {
+    let value = u64::from_le_bytes(randomness[..8].try_into().unwrap());
+    (value % route_count as u64) as usize
+}
+
+/// Start a rate-limited swap for `authority`: claim a fresh `rate_limit`
+/// account, then enforce the cooldown and roll the volume window.
+fn throttle(
+    rate_limit: &mut Account<RateLimit>,
+    authority: Pubkey,
+    bump: u8,
+    config: &RouterConfig,
+) -> Result<()> {
+    if rate_limit.authority == Pubkey::default() {
+        rate_limit.authority = authority;
+        rate_limit.bump = bump;
+    }
+    rate_limit.begin(config, Clock::get()?.slot)
+}
+
+/// Run `strategy` for a two-route (sell, buy) swap; returns whether to buy
+/// and the value the decision was based on.
+fn buy_or_sell(strategy: &Strategy, numbers: &[i64], config: &RouterConfig) -> Result<(bool, i64)> {
+    let (route, value) = strategy.select_route(numbers, config.weighted_target, 2)?;
+    Ok((route == 1, value))
+}
+
+/// Invoke the swap and fail unless `destination` grew by at least `min_amount_out`.
+fn invoke_with_min_out(
+    ix: &Instruction,
+    account_infos: &[AccountInfo],
+    destination: &mut InterfaceAccount<TokenAccount>,
+    min_amount_out: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<SwapFill> {
+    let balance_before = destination.amount;
+
+    invoke_signed(ix, account_infos, signer_seeds)?;
+
+    // Verify what the DEX actually delivered.
+    destination.reload()?;
+    let amount_out = destination
+        .amount
+        .checked_sub(balance_before)
+        .ok_or(ErrorCode::SlippageExceeded)?;
+    require!(amount_out >= min_amount_out, ErrorCode::SlippageExceeded);
+
+    Ok(SwapFill {
+        ix_data_hash: hashv(&[&ix.data]).to_bytes(),
+        output_account: destination.key(),
+        balance_before,
+        balance_after: destination.amount,
+    })
+}
+
+// ============================================================================
+// Instruction Arguments
+// ============================================================================
+
+/// How the inputs are turned into a route choice. Binary strategies pick
+/// route 1 (buy) when their condition holds and route 0 (sell) otherwise.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Odd sum.
+    Parity,
+    /// Sum at or above `threshold`.
+    Threshold { threshold: i64 },
+    /// Strictly positive sum.
+    Sign,
+    /// `sum(weights[i] * numbers[i])` at or above the config's `weighted_target`.
+    WeightedTarget { weights: Vec<i64> },
+    /// Route `sum mod N` out of the N available routes.
+    Modulo,
+}
+
+impl Strategy {
+    /// Pick one of `route_count` routes from `numbers`. Also returns the value
+    /// the decision was based on: the weighted sum for `WeightedTarget`, the
+    /// plain sum otherwise.
+    pub fn select_route(
+        &self,
+        numbers: &[i64],
+        weighted_target: i64,
+        route_count: usize,
+    ) -> Result<(usize, i64)> {
+        require!(!numbers.is_empty(), ErrorCode::NoInputs);
+        require!(numbers.len() <= MAX_INPUTS, ErrorCode::TooManyInputs);
+        require!(route_count > 0, ErrorCode::MissingCpiAccounts);
+
+        let sum: i128 = numbers.iter().map(|n| *n as i128).sum();
+
+        let (route, value) = match self {
+            Strategy::Parity => (usize::from(sum & 1 != 0), sum),
+            Strategy::Threshold { threshold } => {
+                (usize::from(sum >= *threshold as i128), sum)
+            }
+            Strategy::Sign => (usize::from(sum > 0), sum),
+            Strategy::WeightedTarget { weights } => {
+                require!(weights.len() == numbers.len(), ErrorCode::WeightsMismatch);
+                let weighted = numbers
+                    .iter()
+                    .zip(weights)
+                    .try_fold(0i128, |acc, (n, w)| {
+                        (*n as i128)
+                            .checked_mul(*w as i128)
+                            .and_then(|term| acc.checked_add(term))
+                    })
+                    .ok_or(ErrorCode::SumOverflow)?;
+                (usize::from(weighted >= weighted_target as i128), weighted)
+            }
+            Strategy::Modulo => {
+                let route = sum.rem_euclid(route_count as i128) as usize;
+                (route, sum)
+            }
+        };
+
+        // Binary strategies only make sense with exactly two routes.
+        if *self != Strategy::Modulo {
+            require!(route_count == 2, ErrorCode::StrategyRouteMismatch);
+        }
+
+        let value = i64::try_from(value).map_err(|_| ErrorCode::SumOverflow)?;
+        Ok((route, value))
+    }
+}
+
+/// One candidate swap for `execute_parity_swap`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SwapRoute {
+    /// Serialized instruction data forwarded to the DEX.
+    pub ix_data: Vec<u8>,
+    /// Number of `remaining_accounts` this route consumes.
+    pub accounts_len: u8,
+    /// Position, within this route's accounts, of the token account it pays into.
+    pub destination_index: u8,
+    /// Minimum increase of the destination balance.
+    pub min_amount_out: u64,
+}
+
+/// What a forwarded swap delivered, as reported in `SwapDecisionV2`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SwapFill {
+    /// `sha256` of the instruction data sent to the DEX.
+    pub ix_data_hash: [u8; 32],
+    /// Token account the swap paid into.
+    pub output_account: Pubkey,
+    pub balance_before: u64,
+    pub balance_after: u64,
+}
+
+impl SwapFill {
+    pub fn amount_out(&self) -> u64 {
+        self.balance_after - self.balance_before
+    }
+}
+
+/// A writable token account of a vault route, read around the CPI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct TokenBalance {
+    key: Pubkey,
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+}
+
+/// Decision returned by `preview_parity_swap`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SwapPreview {
+    /// Route 1 of a two-route (sell, buy) swap would be taken.
+    pub is_buy: bool,
+    /// Value the strategy decided on (weighted sum for `WeightedTarget`).
+    pub sum: i64,
+    pub route_index: u8,
+    pub selected_accounts: u8,
+    /// Token account the chosen route pays into, and its current balance.
+    pub destination: Pubkey,
+    pub destination_balance: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct SwapAmounts {
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct ClmmSwapAmounts {
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    /// Price limit in Q64.64; 0 lets the CLMM use its own bound.
+    pub sqrt_price_limit_x64: u128,
+}
+
 // ============================================================================
 // Accounts
 // ============================================================================
 
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RouterConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, RouterConfig>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::ParitySwap>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized,
+    )]
+    pub config: Account<'info, RouterConfig>,
+
+    pub admin: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ParitySwap<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
-    /// CHECK: External swap program (e.g. Raydium AMM/CLMM). Validated at CPI.
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RouterConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate_limit", authority.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: External swap program (e.g. Raydium AMM/CLMM); must be on the
+    /// config allowlist.
+    #[account(
+        executable,
+        constraint = config.allowed_programs.contains(&dex_program.key())
+            @ ErrorCode::DexProgramNotAllowed
+    )]
+    pub dex_program: AccountInfo<'info>,
+}
+
+/// Read-only twin of `ParitySwap`, so clients can call the preview as a view.
+#[derive(Accounts)]
+pub struct PreviewParitySwap<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RouterConfig>,
+
+    /// CHECK: External swap program; must be on the config allowlist.
+    #[account(
+        executable,
+        constraint = config.allowed_programs.contains(&dex_program.key())
+            @ ErrorCode::DexProgramNotAllowed
+    )]
+    pub dex_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CommitRandomness<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RouterConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Commitment::INIT_SPACE,
+        seeds = [b"commitment", authority.key().as_ref()],
+        bump
+    )]
+    pub commitment: Account<'info, Commitment>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteCommittedSwap<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RouterConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate_limit", authority.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"commitment", authority.key().as_ref()],
+        bump = commitment.bump,
+        has_one = authority,
+    )]
+    pub commitment: Account<'info, Commitment>,
+
+    /// CHECK: Address-checked `SlotHashes` sysvar, parsed by `find_slot_hash`.
+    #[account(address = sysvar::slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
+
+    /// CHECK: External swap program; must be on the config allowlist.
+    #[account(
+        executable,
+        constraint = config.allowed_programs.contains(&dex_program.key())
+            @ ErrorCode::DexProgramNotAllowed
+    )]
     pub dex_program: AccountInfo<'info>,
 }
 
+#[derive(Accounts)]
+pub struct CancelCommitment<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
+    pub config: Account<'info, RouterConfig>,
+
+    /// Receives the forfeited deposit.
+    #[account(mut)]
+    pub admin: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"commitment", authority.key().as_ref()],
+        bump = commitment.bump,
+        has_one = authority,
+    )]
+    pub commitment: Account<'info, Commitment>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct CreateVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", owner.key().as_ref(), &vault_id.to_le_bytes()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVault<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", owner.key().as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = owner @ ErrorCode::Unauthorized,
+    )]
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromVault<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", owner.key().as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = owner @ ErrorCode::Unauthorized,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut, token::authority = vault)]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteVaultSwap<'info> {
+    /// Vault owner or operator.
+    #[account(
+        mut,
+        constraint = authority.key() == vault.owner || authority.key() == vault.operator
+            @ ErrorCode::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RouterConfig>,
+
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Throttles the vault, whoever trades it.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate_limit", vault.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: External swap program; must be on the config allowlist.
+    #[account(
+        executable,
+        constraint = config.allowed_programs.contains(&dex_program.key())
+            @ ErrorCode::DexProgramNotAllowed
+    )]
+    pub dex_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ParitySwapRaydiumAmm<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RouterConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate_limit", authority.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Raydium AMM v4 program; must be on the config allowlist.
+    #[account(
+        executable,
+        constraint = config.allowed_programs.contains(&amm_program.key())
+            @ ErrorCode::DexProgramNotAllowed
+    )]
+    pub amm_program: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Pool state, validated by the AMM program.
+    #[account(mut)]
+    pub amm: UncheckedAccount<'info>,
+    /// CHECK: Pool authority PDA, validated by the AMM program.
+    pub amm_authority: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM program.
+    #[account(mut)]
+    pub amm_open_orders: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM program.
+    #[account(mut)]
+    pub amm_target_orders: UncheckedAccount<'info>,
+    /// CHECK: Pool coin (base) vault, validated by the AMM program.
+    #[account(mut)]
+    pub pool_coin_vault: UncheckedAccount<'info>,
+    /// CHECK: Pool PC (quote) vault, validated by the AMM program.
+    #[account(mut)]
+    pub pool_pc_vault: UncheckedAccount<'info>,
+    /// CHECK: OpenBook/Serum program, validated by the AMM program.
+    pub market_program: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM program.
+    #[account(mut)]
+    pub market: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM program.
+    #[account(mut)]
+    pub market_bids: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM program.
+    #[account(mut)]
+    pub market_asks: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM program.
+    #[account(mut)]
+    pub market_event_queue: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM program.
+    #[account(mut)]
+    pub market_coin_vault: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM program.
+    #[account(mut)]
+    pub market_pc_vault: UncheckedAccount<'info>,
+    /// CHECK: Validated by the AMM program.
+    pub market_vault_signer: UncheckedAccount<'info>,
+
+    /// User's coin (base) account: paid into on buy, spent on sell.
+    #[account(mut, token::authority = authority)]
+    pub user_coin_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// User's PC (quote) account: spent on buy, paid into on sell.
+    #[account(mut, token::authority = authority)]
+    pub user_pc_account: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+#[derive(Accounts)]
+pub struct ParitySwapRaydiumClmm<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, RouterConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RateLimit::INIT_SPACE,
+        seeds = [b"rate_limit", authority.key().as_ref()],
+        bump
+    )]
+    pub rate_limit: Account<'info, RateLimit>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Raydium CLMM program; must be on the config allowlist.
+    #[account(
+        executable,
+        constraint = config.allowed_programs.contains(&clmm_program.key())
+            @ ErrorCode::DexProgramNotAllowed
+    )]
+    pub clmm_program: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Validated by the CLMM program.
+    pub amm_config: UncheckedAccount<'info>,
+    /// CHECK: Validated by the CLMM program.
+    #[account(mut)]
+    pub pool_state: UncheckedAccount<'info>,
+    /// CHECK: Validated by the CLMM program.
+    #[account(mut)]
+    pub observation_state: UncheckedAccount<'info>,
+    /// CHECK: Pool vault of the base token, validated by the CLMM program.
+    #[account(mut)]
+    pub base_vault: UncheckedAccount<'info>,
+    /// CHECK: Pool vault of the quote token, validated by the CLMM program.
+    #[account(mut)]
+    pub quote_vault: UncheckedAccount<'info>,
+
+    /// User's base account: paid into on buy, spent on sell.
+    #[account(mut, token::authority = authority)]
+    pub user_base_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// User's quote account: spent on buy, paid into on sell.
+    #[account(mut, token::authority = authority)]
+    pub user_quote_account: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+// ============================================================================
+// State
+// ============================================================================
+
+#[account]
+#[derive(InitSpace)]
+pub struct RouterConfig {
+    pub admin: Pubkey,
+    #[max_len(MAX_ALLOWED_PROGRAMS)]
+    pub allowed_programs: Vec<Pubkey>,
+    /// Target compared against by `Strategy::WeightedTarget`.
+    pub weighted_target: i64,
+    /// Minimum slots between two swaps of one authority.
+    pub cooldown_slots: u64,
+    /// Length of the volume window in slots; at least 1 while a cap is set.
+    pub rate_window_slots: u64,
+    /// Maximum amount of `volume_mint` spent per window; 0 means unlimited.
+    pub max_window_volume: u64,
+    /// Input mint the volume cap applies to; swaps spending other mints only
+    /// count towards the cooldown.
+    pub volume_mint: Pubkey,
+    /// Lamports locked by `commit_randomness` and forfeited by
+    /// `cancel_commitment`.
+    pub commit_deposit: u64,
+    pub bump: u8,
+}
+
+impl RouterConfig {
+    /// A zero-slot window would restart on every swap and turn the window cap
+    /// into a per-swap cap, so a cap requires a window.
+    pub fn check_rate_limits(&self) -> Result<()> {
+        require!(
+            self.max_window_volume == 0 || self.rate_window_slots > 0,
+            ErrorCode::InvalidRateWindow
+        );
+        Ok(())
+    }
+}
+
+/// Swap throttling state of one authority (wallet or vault).
+#[account]
+#[derive(InitSpace)]
+pub struct RateLimit {
+    pub authority: Pubkey,
+    /// Slot of the last swap; 0 before the first one.
+    pub last_slot: u64,
+    pub window_start_slot: u64,
+    /// `volume_mint` spent since `window_start_slot`.
+    pub window_volume: u64,
+    pub bump: u8,
+}
+
+impl RateLimit {
+    /// Enforce the cooldown and start a new window if the current one is
+    /// over. Runs before the CPI.
+    pub fn begin(&mut self, config: &RouterConfig, slot: u64) -> Result<()> {
+        if self.last_slot != 0 {
+            let ready = self
+                .last_slot
+                .checked_add(config.cooldown_slots)
+                .ok_or(ErrorCode::SumOverflow)?;
+            require!(slot >= ready, ErrorCode::CooldownActive);
+        }
+
+        let window_end = self
+            .window_start_slot
+            .checked_add(config.rate_window_slots)
+            .ok_or(ErrorCode::SumOverflow)?;
+        if slot >= window_end {
+            self.window_start_slot = slot;
+            self.window_volume = 0;
+        }
+        self.last_slot = slot;
+        Ok(())
+    }
+
+    /// Count `amount_in` of `mint` against the window cap. Typed swaps call
+    /// this before the CPI with their exact input.
+    pub fn spend(&mut self, config: &RouterConfig, mint: &Pubkey, amount_in: u64) -> Result<()> {
+        if *mint != config.volume_mint {
+            return Ok(());
+        }
+        self.window_volume = self
+            .window_volume
+            .checked_add(amount_in)
+            .ok_or(ErrorCode::SumOverflow)?;
+        if config.max_window_volume > 0 {
+            require!(
+                self.window_volume <= config.max_window_volume,
+                ErrorCode::VolumeCapExceeded
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Router-owned vault. The PDA is the owner of the vault's token accounts.
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    pub owner: Pubkey,
+    /// May trade with the vault's funds; `Pubkey::default()` if unset.
+    pub operator: Pubkey,
+    /// Distinguishes several vaults (e.g. one per strategy) of one owner.
+    pub vault_id: u64,
+    /// Mint pairs the vault may trade, set by the owner.
+    #[max_len(MAX_VAULT_LIMITS)]
+    pub limits: Vec<VaultLimit>,
+    pub bump: u8,
+}
+
+/// Owner-set bounds on vault swaps from `input_mint` into `output_mint`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct VaultLimit {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    /// Largest amount of `input_mint` one swap may spend.
+    pub max_amount_in: u64,
+    /// Least `output_mint` received per `input_mint` spent, scaled by
+    /// `PRICE_SCALE`.
+    pub min_price: u64,
+}
+
+/// Pending commit-reveal randomness of one authority.
+#[account]
+#[derive(InitSpace)]
+pub struct Commitment {
+    pub authority: Pubkey,
+    /// `swap_commitment(secret, routes_hash, accounts_hash)`.
+    pub commitment: [u8; 32],
+    /// Slot whose hash is mixed into the randomness.
+    pub slot: u64,
+    /// Lamports held on top of the rent until the reveal.
+    pub deposit: u64,
+    pub bump: u8,
+}
+
 // ============================================================================
 // Events & Errors
 // ============================================================================
 
 #[event]
 pub struct SwapDecision {
+    /// Route 1 of a two-route (sell, buy) swap was taken.
     pub is_buy: bool,
+    /// Value the strategy decided on (weighted sum for `WeightedTarget`).
     pub sum: i64,
     pub selected_accounts: u8,
+    pub strategy: Strategy,
+    pub route_index: u8,
+}
+
+/// `SwapDecision` plus what analytics needs to reconcile the decision with
+/// the fill. Emitted next to `SwapDecision` (and `CommittedSwapDecision`,
+/// where `strategy` is `None` and `input_count`/`sum` are 0).
+#[event]
+pub struct SwapDecisionV2 {
+    /// `SWAP_DECISION_VERSION`.
+    pub version: u8,
+    /// Signer of the swap (owner or operator for vault swaps).
+    pub authority: Pubkey,
+    pub dex_program: Pubkey,
+    pub strategy: Option<Strategy>,
+    pub input_count: u8,
+    /// Value the strategy decided on (weighted sum for `WeightedTarget`).
+    pub sum: i64,
+    pub route_index: u8,
+    pub route_count: u8,
+    pub selected_accounts: u8,
+    pub fill: SwapFill,
+}
+
+/// Everything needed to recompute a committed swap's decision:
+/// `randomness = sha256(secret || slot_hash)` where
+/// `sha256(secret || routes_hash || accounts_hash)` was committed at
+/// `commit_slot`, and `route_index = randomness[..8] mod route_count`.
+#[event]
+pub struct CommittedSwapDecision {
+    pub authority: Pubkey,
+    pub commit_slot: u64,
+    pub secret: [u8; 32],
+    pub slot_hash: [u8; 32],
+    pub randomness: [u8; 32],
+    pub route_index: u8,
+    pub route_count: u8,
+    /// `routes_hash` of the executed routes.
+    pub routes_hash: [u8; 32],
+    /// `accounts_hash` of the route accounts.
+    pub accounts_hash: [u8; 32],
+}
+
+/// A commitment was cancelled without a reveal and its deposit forfeited.
+#[event]
+pub struct CommitmentForfeited {
+    pub authority: Pubkey,
+    pub commit_slot: u64,
+    pub commitment: [u8; 32],
+    pub deposit: u64,
+    pub admin: Pubkey,
 }
 
 #[error_code]
@@ -110,7 +1850,7 @@ pub enum ErrorCode {
     #[msg("Sum exceeds i64 bounds")]
     SumOverflow,
 
-    #[msg("Invalid account split between buy/sell CPIs")]
+    #[msg("Invalid account split between CPI routes")]
     InvalidAccountSplit,
 
     #[msg("CPI account list cannot be empty")]
@@ -118,5 +1858,386 @@ pub enum ErrorCode {
 
     #[msg("Authority signer must be forwarded to CPI")]
     AuthorityNotForwarded,
+
+    #[msg("Signer is not allowed to manage the router config")]
+    Unauthorized,
+
+    #[msg("DEX program is not on the router allowlist")]
+    DexProgramNotAllowed,
+
+    #[msg("Program is already on the allowlist")]
+    ProgramAlreadyAllowed,
+
+    #[msg("Program is not on the allowlist")]
+    ProgramNotInAllowlist,
+
+    #[msg("Allowlist is full")]
+    AllowlistFull,
+
+    #[msg("Swap output is below the requested minimum")]
+    SlippageExceeded,
+
+    #[msg("Strategy weights must match the number of inputs")]
+    WeightsMismatch,
+
+    #[msg("Strategy cannot select among this many routes")]
+    StrategyRouteMismatch,
+
+    #[msg("Too many routes provided")]
+    TooManyRoutes,
+
+    #[msg("Route destination index is out of range")]
+    InvalidDestination,
+
+    #[msg("Revealed secret does not match the commitment")]
+    CommitmentMismatch,
+
+    #[msg("Commitment cannot be revealed in the slot it was made")]
+    RevealTooEarly,
+
+    #[msg("Commitment slot hash is no longer available")]
+    CommitmentExpired,
+
+    #[msg("Commitment can still be revealed")]
+    CommitmentNotExpired,
+
+    #[msg("Vault swaps must pay into a vault-owned token account")]
+    DestinationNotVault,
+
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+
+    #[msg("Swap cooldown has not elapsed")]
+    CooldownActive,
+
+    #[msg("Swap volume cap for this window reached")]
+    VolumeCapExceeded,
+
+    #[msg("Vault swap credited tokens to an account the vault does not own")]
+    OutputNotVault,
+
+    #[msg("Vault already has the maximum number of limits")]
+    VaultLimitsFull,
+
+    #[msg("Vault has no limit for this mint pair")]
+    VaultPairNotAllowed,
+
+    #[msg("Vault swap spends more than its limit allows")]
+    VaultAmountInExceeded,
+
+    #[msg("Vault swap price is below its limit")]
+    VaultPriceTooLow,
+
+    #[msg("A volume cap needs a rate window of at least one slot")]
+    InvalidRateWindow,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_strategies_pick_buy_or_sell() {
+        let numbers = [4, -1];
+        assert_eq!(Strategy::Parity.select_route(&numbers, 0, 2).unwrap(), (1, 3));
+        assert_eq!(
+            Strategy::Threshold { threshold: 4 }.select_route(&numbers, 0, 2).unwrap(),
+            (0, 3)
+        );
+        assert_eq!(Strategy::Sign.select_route(&[-2, 1], 0, 2).unwrap(), (0, -1));
+
+        let weighted = Strategy::WeightedTarget { weights: vec![2, 3] };
+        assert_eq!(weighted.select_route(&numbers, 5, 2).unwrap(), (1, 5));
+        assert_eq!(weighted.select_route(&numbers, 6, 2).unwrap(), (0, 5));
+    }
+
+    #[test]
+    fn modulo_handles_negative_sums() {
+        assert_eq!(Strategy::Modulo.select_route(&[-1], 0, 3).unwrap(), (2, -1));
+        assert_eq!(Strategy::Modulo.select_route(&[7], 0, 3).unwrap(), (1, 7));
+        // Modulo over two routes agrees with parity.
+        for n in -5..5 {
+            assert_eq!(
+                Strategy::Modulo.select_route(&[n], 0, 2).unwrap(),
+                Strategy::Parity.select_route(&[n], 0, 2).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_inputs() {
+        assert!(Strategy::Parity.select_route(&[], 0, 2).is_err());
+        assert!(Strategy::Parity.select_route(&[1], 0, 3).is_err());
+        assert!(Strategy::WeightedTarget { weights: vec![1] }
+            .select_route(&[1, 2], 0, 2)
+            .is_err());
+        assert!(Strategy::WeightedTarget { weights: vec![i64::MAX, i64::MAX, i64::MAX] }
+            .select_route(&[i64::MAX, i64::MAX, i64::MAX], 0, 2)
+            .is_err());
+    }
+
+    #[test]
+    fn finds_slot_hash_entries() {
+        let mut data = 2u64.to_le_bytes().to_vec();
+        for (slot, byte) in [(12u64, 0xbb), (10u64, 0xaa)] {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[byte; 32]);
+        }
+        assert_eq!(find_slot_hash(&data, 10), Some([0xaa; 32]));
+        assert_eq!(find_slot_hash(&data, 12), Some([0xbb; 32]));
+        assert_eq!(find_slot_hash(&data, 11), None);
+        assert_eq!(find_slot_hash(&data[..4], 10), None);
+    }
+
+    #[test]
+    fn committed_route_is_reproducible() {
+        let randomness = committed_randomness(&[1; 32], &[2; 32]);
+        assert_eq!(randomness, hashv(&[&[1; 32], &[2; 32]]).to_bytes());
+
+        let value = u64::from_le_bytes(randomness[..8].try_into().unwrap());
+        assert_eq!(committed_route(&randomness, 2), (value & 1) as usize);
+        assert_eq!(committed_route(&randomness, 5), (value % 5) as usize);
+    }
+
+    #[test]
+    fn commitment_covers_routes_and_accounts() {
+        let route = |min_amount_out| SwapRoute {
+            ix_data: vec![9, 1, 2],
+            accounts_len: 2,
+            destination_index: 1,
+            min_amount_out,
+        };
+        let routes = [route(5), route(6)];
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let mut encoded = 2u32.to_le_bytes().to_vec();
+        for min_amount_out in [5u64, 6] {
+            encoded.extend([3, 0, 0, 0, 9, 1, 2, 2, 1]);
+            encoded.extend(min_amount_out.to_le_bytes());
+        }
+        assert_eq!(routes_hash(&routes).unwrap(), hashv(&[&encoded]).to_bytes());
+        assert_eq!(
+            accounts_hash(&keys),
+            hashv(&[keys[0].as_ref(), keys[1].as_ref()]).to_bytes()
+        );
+
+        let secret = [7; 32];
+        let committed = swap_commitment(
+            &secret,
+            &routes_hash(&routes).unwrap(),
+            &accounts_hash(&keys),
+        );
+        let swapped_routes = swap_commitment(
+            &secret,
+            &routes_hash(&[route(6), route(5)]).unwrap(),
+            &accounts_hash(&keys),
+        );
+        let swapped_keys = swap_commitment(
+            &secret,
+            &routes_hash(&routes).unwrap(),
+            &accounts_hash([&keys[1], &keys[0]]),
+        );
+        assert_ne!(committed, swapped_routes);
+        assert_ne!(committed, swapped_keys);
+    }
+
+    #[test]
+    fn vault_output_cannot_leave_the_vault() {
+        let vault = Pubkey::new_unique();
+        let (coin, pc, pool) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let balance = |mint, owner, amount| TokenBalance {
+            key: Pubkey::new_unique(),
+            mint,
+            owner,
+            amount,
+        };
+        // Vault source, vault destination, pool reserves, someone else's account.
+        let before = [
+            balance(pc, vault, 100),
+            balance(coin, vault, 0),
+            balance(pc, pool, 1_000),
+            balance(coin, pool, 1_000),
+            balance(coin, Pubkey::new_unique(), 0),
+        ];
+        let after = |amounts: [u64; 5]| {
+            let mut after = before;
+            for (balance, amount) in after.iter_mut().zip(amounts) {
+                balance.amount = amount;
+            }
+            after
+        };
+
+        // Output into the vault, input into the pool.
+        assert!(check_vault_flow(&vault, &before, &after([60, 38, 1_040, 962, 0])).is_ok());
+        // Output diverted to a foreign account.
+        assert!(check_vault_flow(&vault, &before, &after([60, 0, 1_040, 962, 38])).is_err());
+        // The pool credited more of the input mint than the vault paid.
+        assert!(check_vault_flow(&vault, &before, &after([60, 38, 1_041, 962, 0])).is_err());
+        assert_eq!(spent(&vault, &pc, &before, &after([60, 38, 1_040, 962, 0])), 40);
+        assert_eq!(spent(&vault, &coin, &before, &after([60, 38, 1_040, 962, 0])), 0);
+        // An account changed hands during the CPI.
+        let mut stolen = after([60, 38, 1_040, 962, 0]);
+        stolen[1].owner = Pubkey::new_unique();
+        assert!(check_vault_flow(&vault, &before, &stolen).is_err());
+    }
+
+    #[test]
+    fn vault_trades_stay_within_the_owners_limits() {
+        let vault = Pubkey::new_unique();
+        let (coin, pc, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let balance = |mint, amount| TokenBalance {
+            key: Pubkey::new_unique(),
+            mint,
+            owner: vault,
+            amount,
+        };
+        let before = [balance(pc, 100), balance(coin, 0), balance(other, 100)];
+        let after = |pc_left, other_left| {
+            let mut after = before;
+            after[0].amount = pc_left;
+            after[2].amount = other_left;
+            after
+        };
+        // At most 50 pc per swap, for at least 0.5 coin each.
+        let limits = [VaultLimit {
+            input_mint: pc,
+            output_mint: coin,
+            max_amount_in: 50,
+            min_price: PRICE_SCALE / 2,
+        }];
+        let check = |limits: &[VaultLimit], output_mint, amount_out, after: &[TokenBalance]| {
+            check_vault_limits(limits, &vault, output_mint, amount_out, &before, after)
+        };
+
+        assert!(check(&limits, &coin, 20, &after(60, 100)).is_ok());
+        assert_eq!(
+            check(&limits, &coin, 19, &after(60, 100)).unwrap_err(),
+            ErrorCode::VaultPriceTooLow.into()
+        );
+        assert_eq!(
+            check(&limits, &coin, 100, &after(49, 100)).unwrap_err(),
+            ErrorCode::VaultAmountInExceeded.into()
+        );
+        // No limit for the pair, a pair without a limit, or a second input.
+        assert_eq!(
+            check(&[], &coin, 20, &after(60, 100)).unwrap_err(),
+            ErrorCode::VaultPairNotAllowed.into()
+        );
+        assert_eq!(
+            check(&limits, &pc, 20, &after(100, 60)).unwrap_err(),
+            ErrorCode::VaultPairNotAllowed.into()
+        );
+        assert_eq!(
+            check(&limits, &coin, 20, &after(60, 99)).unwrap_err(),
+            ErrorCode::VaultPairNotAllowed.into()
+        );
+        // Spending nothing needs no limit.
+        assert!(check(&[], &coin, 0, &before).is_ok());
+    }
+
+    fn limits(cooldown_slots: u64, rate_window_slots: u64, max_window_volume: u64) -> RouterConfig {
+        RouterConfig {
+            admin: Pubkey::default(),
+            allowed_programs: Vec::new(),
+            weighted_target: 0,
+            cooldown_slots,
+            rate_window_slots,
+            max_window_volume,
+            volume_mint: Pubkey::default(),
+            commit_deposit: 0,
+            bump: 0,
+        }
+    }
+
+    fn rate_limit() -> RateLimit {
+        RateLimit {
+            authority: Pubkey::default(),
+            last_slot: 0,
+            window_start_slot: 0,
+            window_volume: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn rate_limit_enforces_cooldown() {
+        let config = limits(10, 0, 0);
+        let mut state = rate_limit();
+        state.begin(&config, 5).unwrap();
+        assert!(state.begin(&config, 14).is_err());
+        state.begin(&config, 15).unwrap();
+        assert_eq!(state.last_slot, 15);
+    }
+
+    #[test]
+    fn rate_limit_caps_window_volume() {
+        let config = limits(0, 100, 1_000);
+        let mut state = rate_limit();
+        let mint = config.volume_mint;
+        state.begin(&config, 100).unwrap();
+        state.spend(&config, &mint, 600).unwrap();
+        state.begin(&config, 150).unwrap();
+        assert!(state.spend(&config, &mint, 500).is_err());
+        // Other input mints are not capped.
+        state.spend(&config, &Pubkey::new_unique(), 5_000).unwrap();
+
+        // A new window starts from zero.
+        let mut state = RateLimit { window_volume: 600, ..state };
+        state.begin(&config, 200).unwrap();
+        assert_eq!(state.window_start_slot, 200);
+        state.spend(&config, &mint, 1_000).unwrap();
+    }
+
+    #[test]
+    fn volume_caps_need_a_window() {
+        assert!(limits(10, 0, 0).check_rate_limits().is_ok());
+        assert!(limits(0, 100, 1_000).check_rate_limits().is_ok());
+        assert_eq!(
+            limits(0, 0, 1_000).check_rate_limits().unwrap_err(),
+            ErrorCode::InvalidRateWindow.into()
+        );
+
+        // What such a window would do: every swap restarts it, so the cap
+        // only bounds each swap on its own.
+        let config = limits(0, 0, 1_000);
+        let mut state = rate_limit();
+        let mint = config.volume_mint;
+        for slot in [100, 101] {
+            state.begin(&config, slot).unwrap();
+            state.spend(&config, &mint, 1_000).unwrap();
+        }
+    }
+
+    #[test]
+    fn only_the_route_signer_is_forwarded_as_signer() {
+        let (authority, other, vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let owner = Pubkey::default();
+        let mut lamports = [0u64; 3];
+        let [l0, l1, l2] = &mut lamports;
+        let accounts = [
+            AccountInfo::new(&authority, true, false, l0, &mut [], &owner, false, 0),
+            AccountInfo::new(&other, true, true, l1, &mut [], &owner, false, 0),
+            AccountInfo::new(&vault, false, true, l2, &mut [], &owner, false, 0),
+        ];
+
+        let metas = route_metas(&accounts, &RouteSigner::Wallet(authority));
+        assert_eq!(
+            metas,
+            vec![
+                AccountMeta::new_readonly(authority, true),
+                AccountMeta::new(other, false),
+                AccountMeta::new(vault, false),
+            ]
+        );
+
+        let metas = route_metas(&accounts, &RouteSigner::Vault { key: vault, seeds: &[], limits: &[] });
+        assert_eq!(
+            metas,
+            vec![
+                AccountMeta::new_readonly(authority, false),
+                AccountMeta::new(other, false),
+                AccountMeta::new(vault, true),
+            ]
+        );
+    }
+}
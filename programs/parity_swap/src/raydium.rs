@@ -0,0 +1,205 @@
+//! Instruction builders for the Raydium swap programs the router can target.
+//!
+//! These mirror Raydium's own client code so callers only pass amounts and
+//! pool accounts; the byte layout and account ordering live here.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+/// AMM v4 `swap_base_in` instruction tag.
+pub const AMM_V4_SWAP_BASE_IN: u8 = 9;
+
+/// Number of accounts in an AMM v4 `swap_base_in` instruction.
+pub const AMM_V4_SWAP_ACCOUNTS_LEN: usize = 18;
+
+/// CLMM `swap` discriminator (`sha256("global:swap")[..8]`).
+pub const CLMM_SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+/// Pool-side accounts of a Raydium AMM v4 swap (OpenBook market included).
+pub struct AmmV4Pool {
+    pub token_program: Pubkey,
+    pub amm: Pubkey,
+    pub amm_authority: Pubkey,
+    pub amm_open_orders: Pubkey,
+    pub amm_target_orders: Pubkey,
+    pub pool_coin_vault: Pubkey,
+    pub pool_pc_vault: Pubkey,
+    pub market_program: Pubkey,
+    pub market: Pubkey,
+    pub market_bids: Pubkey,
+    pub market_asks: Pubkey,
+    pub market_event_queue: Pubkey,
+    pub market_coin_vault: Pubkey,
+    pub market_pc_vault: Pubkey,
+    pub market_vault_signer: Pubkey,
+}
+
+/// Build an AMM v4 `swap_base_in`: spend exactly `amount_in` of `user_source`
+/// and receive at least `min_amount_out` into `user_destination`.
+pub fn amm_v4_swap_base_in(
+    program_id: Pubkey,
+    pool: &AmmV4Pool,
+    user_source: Pubkey,
+    user_destination: Pubkey,
+    user_owner: Pubkey,
+    amount_in: u64,
+    min_amount_out: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(17);
+    data.push(AMM_V4_SWAP_BASE_IN);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&min_amount_out.to_le_bytes());
+
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(pool.token_program, false),
+            AccountMeta::new(pool.amm, false),
+            AccountMeta::new_readonly(pool.amm_authority, false),
+            AccountMeta::new(pool.amm_open_orders, false),
+            AccountMeta::new(pool.amm_target_orders, false),
+            AccountMeta::new(pool.pool_coin_vault, false),
+            AccountMeta::new(pool.pool_pc_vault, false),
+            AccountMeta::new_readonly(pool.market_program, false),
+            AccountMeta::new(pool.market, false),
+            AccountMeta::new(pool.market_bids, false),
+            AccountMeta::new(pool.market_asks, false),
+            AccountMeta::new(pool.market_event_queue, false),
+            AccountMeta::new(pool.market_coin_vault, false),
+            AccountMeta::new(pool.market_pc_vault, false),
+            AccountMeta::new_readonly(pool.market_vault_signer, false),
+            AccountMeta::new(user_source, false),
+            AccountMeta::new(user_destination, false),
+            AccountMeta::new_readonly(user_owner, true),
+        ],
+        data,
+    }
+}
+
+/// Accounts of one direction of a Raydium CLMM swap.
+pub struct ClmmSwapAccounts {
+    pub payer: Pubkey,
+    pub amm_config: Pubkey,
+    pub pool_state: Pubkey,
+    pub input_token_account: Pubkey,
+    pub output_token_account: Pubkey,
+    pub input_vault: Pubkey,
+    pub output_vault: Pubkey,
+    pub observation_state: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// Build a CLMM `swap`. The first tick array is a named account of the
+/// instruction, any further ones follow as remaining accounts.
+pub fn clmm_swap(
+    program_id: Pubkey,
+    keys: &ClmmSwapAccounts,
+    tick_arrays: &[Pubkey],
+    amount: u64,
+    other_amount_threshold: u64,
+    sqrt_price_limit_x64: u128,
+    is_base_input: bool,
+) -> Instruction {
+    let mut data = Vec::with_capacity(41);
+    data.extend_from_slice(&CLMM_SWAP_DISCRIMINATOR);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&other_amount_threshold.to_le_bytes());
+    data.extend_from_slice(&sqrt_price_limit_x64.to_le_bytes());
+    data.push(is_base_input as u8);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(keys.payer, true),
+        AccountMeta::new_readonly(keys.amm_config, false),
+        AccountMeta::new(keys.pool_state, false),
+        AccountMeta::new(keys.input_token_account, false),
+        AccountMeta::new(keys.output_token_account, false),
+        AccountMeta::new(keys.input_vault, false),
+        AccountMeta::new(keys.output_vault, false),
+        AccountMeta::new(keys.observation_state, false),
+        AccountMeta::new_readonly(keys.token_program, false),
+    ];
+    accounts.extend(tick_arrays.iter().map(|key| AccountMeta::new(*key, false)));
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool() -> AmmV4Pool {
+        AmmV4Pool {
+            token_program: Pubkey::new_unique(),
+            amm: Pubkey::new_unique(),
+            amm_authority: Pubkey::new_unique(),
+            amm_open_orders: Pubkey::new_unique(),
+            amm_target_orders: Pubkey::new_unique(),
+            pool_coin_vault: Pubkey::new_unique(),
+            pool_pc_vault: Pubkey::new_unique(),
+            market_program: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            market_bids: Pubkey::new_unique(),
+            market_asks: Pubkey::new_unique(),
+            market_event_queue: Pubkey::new_unique(),
+            market_coin_vault: Pubkey::new_unique(),
+            market_pc_vault: Pubkey::new_unique(),
+            market_vault_signer: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn amm_v4_swap_base_in_layout() {
+        let pool = pool();
+        let (source, destination, owner) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ix = amm_v4_swap_base_in(Pubkey::new_unique(), &pool, source, destination, owner, 5, 7);
+
+        let mut expected = vec![9u8];
+        expected.extend_from_slice(&5u64.to_le_bytes());
+        expected.extend_from_slice(&7u64.to_le_bytes());
+        assert_eq!(ix.data, expected);
+
+        assert_eq!(ix.accounts.len(), AMM_V4_SWAP_ACCOUNTS_LEN);
+        assert_eq!(ix.accounts[1].pubkey, pool.amm);
+        assert_eq!(ix.accounts[14].pubkey, pool.market_vault_signer);
+        assert_eq!(ix.accounts[15].pubkey, source);
+        assert_eq!(ix.accounts[16].pubkey, destination);
+        assert_eq!(ix.accounts[17].pubkey, owner);
+        assert!(ix.accounts[17].is_signer);
+        assert_eq!(ix.accounts.iter().filter(|m| m.is_signer).count(), 1);
+    }
+
+    #[test]
+    fn clmm_swap_layout() {
+        let keys = ClmmSwapAccounts {
+            payer: Pubkey::new_unique(),
+            amm_config: Pubkey::new_unique(),
+            pool_state: Pubkey::new_unique(),
+            input_token_account: Pubkey::new_unique(),
+            output_token_account: Pubkey::new_unique(),
+            input_vault: Pubkey::new_unique(),
+            output_vault: Pubkey::new_unique(),
+            observation_state: Pubkey::new_unique(),
+            token_program: Pubkey::new_unique(),
+        };
+        let ticks = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let ix = clmm_swap(Pubkey::new_unique(), &keys, &ticks, 100, 90, 0, true);
+
+        assert_eq!(ix.data.len(), 41);
+        assert_eq!(ix.data[..8], CLMM_SWAP_DISCRIMINATOR);
+        assert_eq!(ix.data[8..16], 100u64.to_le_bytes());
+        assert_eq!(ix.data[16..24], 90u64.to_le_bytes());
+        assert_eq!(ix.data[24..40], 0u128.to_le_bytes());
+        assert_eq!(ix.data[40], 1);
+
+        assert_eq!(ix.accounts.len(), 11);
+        assert!(ix.accounts[0].is_signer);
+        assert_eq!(ix.accounts[3].pubkey, keys.input_token_account);
+        assert_eq!(ix.accounts[9].pubkey, ticks[0]);
+        assert_eq!(ix.accounts[10].pubkey, ticks[1]);
+    }
+}
@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, Transfer};
+use anchor_spl::token::{self, Burn, CloseAccount, Token, TokenAccount, Mint, MintTo, SetAuthority, Transfer};
+use anchor_spl::token::spl_token::instruction::AuthorityType;
 use anchor_spl::associated_token::AssociatedToken;
+pub use vectaiproj_vesting::{GrantSchedule, Schedule};
 
 declare_id!("ETe5hWKprkrRVBryrhvPVDPS37ea4U9iZ7p6pv78Kusf");
 
@@ -10,6 +12,7 @@ pub const USDC_DECIMALS: u32 = 6;
 pub const MIN_PURCHASE_USDC: u64 = 10_000_000; // 10 USDC with 6 decimals
 pub const MAX_CLIFF_DURATION: i64 = 730 * 24 * 60 * 60; // 2 years max
 pub const MAX_VESTING_DURATION: i64 = 1460 * 24 * 60 * 60; // 4 years max
+pub const MAX_BATCH_CLAIMS: usize = 16; // (Vesting, token account) pairs per batch_claim
 
 #[program]
 pub mod vesting_sale {
@@ -25,10 +28,7 @@ pub mod vesting_sale {
         let sale_state = &mut ctx.accounts.sale_state;
         
         // Validate parameters
-        require!(cliff_duration > 0, ErrorCode::InvalidCliffDuration);
-        require!(cliff_duration <= MAX_CLIFF_DURATION, ErrorCode::InvalidCliffDuration);
-        require!(vesting_duration > 0, ErrorCode::InvalidVestingDuration);
-        require!(vesting_duration <= MAX_VESTING_DURATION, ErrorCode::InvalidVestingDuration);
+        validate_schedule(cliff_duration, vesting_duration)?;
         require!(usdc_price_per_vect > 0, ErrorCode::InvalidPrice);
         
         // Validate mint decimals
@@ -53,8 +53,11 @@ pub mod vesting_sale {
         
         sale_state.total_vect_sold = 0;
         sale_state.total_usdc_raised = 0;
+        sale_state.total_vect_granted = 0;
+        sale_state.total_vect_claimed = 0;
         sale_state.is_paused = false;
         sale_state.is_ended = false;
+        sale_state.vesting_transfers_enabled = false;
         sale_state.bump = ctx.bumps.sale_state;
         
         msg!("Sale initialized with price: {} USDC per VECT", usdc_price_per_vect);
@@ -93,35 +96,24 @@ pub mod vesting_sale {
         let sale_state = &mut ctx.accounts.sale_state;
         let clock = Clock::get()?;
         
-        // Check sale status
-        require!(!sale_state.is_paused, ErrorCode::SaleIsPaused);
-        require!(!sale_state.is_ended, ErrorCode::SaleHasEnded);
-        
-        // Validate minimum purchase
-        require!(usdc_amount >= MIN_PURCHASE_USDC, ErrorCode::BelowMinimumPurchase);
-        
-        // Calculate VECT amount with proper decimal handling
-        // Formula: vect_amount = (usdc_amount * 10^VECT_DECIMALS) / usdc_price_per_vect
-        let vect_amount = calculate_vect_amount(
+        // Validate the purchase and update sale statistics
+        let vect_amount = record_purchase(
+            sale_state,
+            ctx.accounts.vect_vault.amount,
             usdc_amount,
-            sale_state.usdc_price_per_vect,
         )?;
         
-        require!(vect_amount > 0, ErrorCode::InvalidAmount);
-        
-        // Check vault has enough tokens
-        require!(
-            ctx.accounts.vect_vault.amount >= vect_amount,
-            ErrorCode::InsufficientVaultBalance
-        );
-        
         // Update state BEFORE external CPI (checks-effects-interactions pattern)
         let vesting = &mut ctx.accounts.vesting;
         
-        if vesting.beneficiary == Pubkey::default() {
-            // Initialize new vesting
+        if vesting.beneficiary == Pubkey::default()
+            || vesting.claimed_amount == vesting.total_vect_amount
+        {
+            // Initialize new vesting, or restart one that is fully claimed so
+            // the new tokens do not inherit an old start time
             vesting.beneficiary = ctx.accounts.buyer.key();
             vesting.sale_state = sale_state.key();
+            vesting.position_mint = Pubkey::default();
             vesting.total_vect_amount = vect_amount;
             vesting.claimed_amount = 0;
             vesting.start_time = clock.unix_timestamp;
@@ -133,14 +125,6 @@ pub mod vesting_sale {
                 .ok_or(ErrorCode::MathOverflow)?;
         }
         
-        // Update sale statistics
-        sale_state.total_vect_sold = sale_state.total_vect_sold
-            .checked_add(vect_amount)
-            .ok_or(ErrorCode::MathOverflow)?;
-        sale_state.total_usdc_raised = sale_state.total_usdc_raised
-            .checked_add(usdc_amount)
-            .ok_or(ErrorCode::MathOverflow)?;
-        
         // Transfer USDC from buyer to treasury (AFTER state updates)
         let cpi_accounts = Transfer {
             from: ctx.accounts.buyer_usdc_account.to_account_info(),
@@ -157,10 +141,81 @@ pub mod vesting_sale {
         Ok(())
     }
 
+    /// Buy VECT with USDC and receive the vesting as a position NFT
+    ///
+    /// Instead of binding the vesting to the buyer's wallet, a fresh 0-decimal
+    /// mint with a supply of one is created and whoever holds that token can
+    /// claim, so positions can be traded or moved between wallets.
+    pub fn buy_position_with_usdc(
+        ctx: Context<BuyPositionWithUsdc>,
+        usdc_amount: u64,
+    ) -> Result<()> {
+        let sale_state = &mut ctx.accounts.sale_state;
+        let clock = Clock::get()?;
+        
+        let vect_amount = record_purchase(
+            sale_state,
+            ctx.accounts.vect_vault.amount,
+            usdc_amount,
+        )?;
+        
+        let vesting = &mut ctx.accounts.position_vesting;
+        vesting.beneficiary = Pubkey::default();
+        vesting.sale_state = sale_state.key();
+        vesting.position_mint = ctx.accounts.position_mint.key();
+        vesting.total_vect_amount = vect_amount;
+        vesting.claimed_amount = 0;
+        vesting.start_time = clock.unix_timestamp;
+        vesting.bump = ctx.bumps.position_vesting;
+        
+        // Transfer USDC from buyer to treasury (AFTER state updates)
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.buyer_usdc_account.to_account_info(),
+            to: ctx.accounts.usdc_treasury.to_account_info(),
+            authority: ctx.accounts.buyer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), usdc_amount)?;
+        
+        // Mint the single position token, then drop the mint authority so the
+        // supply can never exceed one
+        let sale_authority = sale_state.authority;
+        let seeds = &[
+            b"sale",
+            sale_authority.as_ref(),
+            &[sale_state.bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.position_mint.to_account_info(),
+            to: ctx.accounts.buyer_position_account.to_account_info(),
+            authority: ctx.accounts.sale_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::mint_to(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer), 1)?;
+        
+        let cpi_accounts = SetAuthority {
+            account_or_mint: ctx.accounts.position_mint.to_account_info(),
+            current_authority: ctx.accounts.sale_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::set_authority(
+            CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+        
+        msg!("Purchased {} VECT with {} USDC as position {}",
+            vect_amount, usdc_amount, ctx.accounts.position_mint.key());
+        
+        Ok(())
+    }
+
     /// Claim vested tokens according to the schedule
     pub fn claim(ctx: Context<Claim>) -> Result<()> {
         let vesting = &mut ctx.accounts.vesting;
-        let sale_state = &ctx.accounts.sale_state;
+        let sale_state = &mut ctx.accounts.sale_state;
         let clock = Clock::get()?;
         
         // Calculate elapsed time since vesting start
@@ -170,25 +225,8 @@ pub mod vesting_sale {
         
         require!(elapsed >= sale_state.cliff_duration, ErrorCode::CliffNotReached);
         
-        // Calculate vested amount
-        let vested_amount = if elapsed >= sale_state.cliff_duration + sale_state.vesting_duration {
-            // Fully vested - give all remaining to avoid rounding dust
-            vesting.total_vect_amount
-        } else {
-            // Linear vesting after cliff
-            let vesting_elapsed = elapsed - sale_state.cliff_duration;
-            let vested = (vesting.total_vect_amount as u128)
-                .checked_mul(vesting_elapsed as u128)
-                .ok_or(ErrorCode::MathOverflow)?
-                .checked_div(sale_state.vesting_duration as u128)
-                .ok_or(ErrorCode::MathOverflow)?;
-            u64::try_from(vested).map_err(|_| ErrorCode::MathOverflow)?
-        };
-        
         // Calculate claimable amount
-        let claimable = vested_amount
-            .checked_sub(vesting.claimed_amount)
-            .ok_or(ErrorCode::MathOverflow)?;
+        let claimable = vesting.claimable(sale_state, clock.unix_timestamp)?;
         
         require!(claimable > 0, ErrorCode::NothingToClaim);
         
@@ -196,11 +234,15 @@ pub mod vesting_sale {
         vesting.claimed_amount = vesting.claimed_amount
             .checked_add(claimable)
             .ok_or(ErrorCode::MathOverflow)?;
+        sale_state.total_vect_claimed = sale_state.total_vect_claimed
+            .checked_add(claimable)
+            .ok_or(ErrorCode::MathOverflow)?;
         
         // Transfer tokens from vault to beneficiary using PDA signer
+        let sale_authority = sale_state.authority;
         let seeds = &[
             b"sale",
-            sale_state.authority.as_ref(),
+            sale_authority.as_ref(),
             &[sale_state.bump],
         ];
         let signer = &[&seeds[..]];
@@ -221,6 +263,419 @@ pub mod vesting_sale {
         Ok(())
     }
 
+    /// Claim vested tokens of a position NFT; the current holder receives them
+    pub fn claim_position(ctx: Context<ClaimPosition>) -> Result<()> {
+        let vesting = &mut ctx.accounts.position_vesting;
+        let sale_state = &mut ctx.accounts.sale_state;
+        let clock = Clock::get()?;
+        
+        let elapsed = clock.unix_timestamp
+            .checked_sub(vesting.start_time)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(elapsed >= sale_state.cliff_duration, ErrorCode::CliffNotReached);
+        
+        let claimable = vesting.claimable(sale_state, clock.unix_timestamp)?;
+        
+        require!(claimable > 0, ErrorCode::NothingToClaim);
+        
+        // Update claimed amount BEFORE transfer
+        vesting.claimed_amount = vesting.claimed_amount
+            .checked_add(claimable)
+            .ok_or(ErrorCode::MathOverflow)?;
+        sale_state.total_vect_claimed = sale_state.total_vect_claimed
+            .checked_add(claimable)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        let sale_authority = sale_state.authority;
+        let seeds = &[
+            b"sale",
+            sale_authority.as_ref(),
+            &[sale_state.bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vect_vault.to_account_info(),
+            to: ctx.accounts.holder_vect_account.to_account_info(),
+            authority: ctx.accounts.sale_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        
+        token::transfer(cpi_ctx, claimable)?;
+        
+        msg!("Claimed {} VECT tokens for position {} (Total claimed: {}/{})",
+            claimable, vesting.position_mint, vesting.claimed_amount, vesting.total_vect_amount);
+        
+        Ok(())
+    }
+
+    /// Claim vested tokens for many buyers in one transaction
+    ///
+    /// `remaining_accounts` holds (Vesting, beneficiary VECT token account) pairs.
+    /// Anyone may crank this since tokens only ever go to the beneficiary's own
+    /// account; vestings with nothing claimable are skipped rather than failing.
+    pub fn batch_claim<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchClaim<'info>>,
+    ) -> Result<()> {
+        let sale_state = &ctx.accounts.sale_state;
+        let clock = Clock::get()?;
+        
+        let remaining = ctx.remaining_accounts;
+        require!(
+            !remaining.is_empty() && remaining.len().is_multiple_of(2),
+            ErrorCode::InvalidBatchAccounts
+        );
+        require!(
+            remaining.len() / 2 <= MAX_BATCH_CLAIMS,
+            ErrorCode::BatchTooLarge
+        );
+        
+        let seeds = &[
+            b"sale",
+            sale_state.authority.as_ref(),
+            &[sale_state.bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        let mut paid_accounts: u32 = 0;
+        let mut skipped_accounts: u32 = 0;
+        let mut total_claimed: u64 = 0;
+        
+        for pair in remaining.chunks_exact(2) {
+            let vesting_info = &pair[0];
+            let destination_info = &pair[1];
+            
+            require!(vesting_info.is_writable, ErrorCode::InvalidVestingAccount);
+            let mut vesting = Account::<Vesting>::try_from(vesting_info)?;
+            require_keys_eq!(
+                vesting.sale_state,
+                sale_state.key(),
+                ErrorCode::InvalidVestingAccount
+            );
+            // Position NFTs pay whoever holds them, which cannot be checked here
+            require_keys_eq!(
+                vesting.position_mint,
+                Pubkey::default(),
+                ErrorCode::InvalidVestingAccount
+            );
+            
+            let destination = Account::<TokenAccount>::try_from(destination_info)?;
+            require_keys_eq!(
+                destination.owner,
+                vesting.beneficiary,
+                ErrorCode::InvalidBeneficiaryAccount
+            );
+            require_keys_eq!(
+                destination.mint,
+                sale_state.vect_mint,
+                ErrorCode::InvalidBeneficiaryAccount
+            );
+            
+            let claimable = vesting.claimable(sale_state, clock.unix_timestamp)?;
+            
+            if claimable == 0 {
+                skipped_accounts += 1;
+                continue;
+            }
+            
+            // Persist the claim BEFORE transfer so a duplicated pair sees it
+            vesting.claimed_amount = vesting.claimed_amount
+                .checked_add(claimable)
+                .ok_or(ErrorCode::MathOverflow)?;
+            vesting.exit(&crate::ID)?;
+            
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vect_vault.to_account_info(),
+                to: destination_info.clone(),
+                authority: ctx.accounts.sale_state.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            
+            token::transfer(cpi_ctx, claimable)?;
+            
+            paid_accounts += 1;
+            total_claimed = total_claimed
+                .checked_add(claimable)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+        
+        let sale_state = &mut ctx.accounts.sale_state;
+        sale_state.total_vect_claimed = sale_state.total_vect_claimed
+            .checked_add(total_claimed)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        msg!("Batch claimed {} VECT for {} accounts ({} skipped)",
+            total_claimed, paid_accounts, skipped_accounts);
+        
+        Ok(())
+    }
+
+    /// Move the unclaimed part of a vesting position to a new beneficiary
+    ///
+    /// The new `Vesting` keeps the original start time and claimed amount so the
+    /// unlock schedule is unchanged; the old account is closed to the signer.
+    pub fn transfer_vesting(ctx: Context<TransferVesting>) -> Result<()> {
+        let sale_state = &ctx.accounts.sale_state;
+        let vesting = &ctx.accounts.vesting;
+        
+        require!(
+            sale_state.vesting_transfers_enabled,
+            ErrorCode::VestingTransfersDisabled
+        );
+        require_keys_neq!(
+            ctx.accounts.new_beneficiary.key(),
+            ctx.accounts.beneficiary.key(),
+            ErrorCode::InvalidNewBeneficiary
+        );
+        require!(
+            vesting.claimed_amount < vesting.total_vect_amount,
+            ErrorCode::NothingToTransfer
+        );
+        
+        let new_vesting = &mut ctx.accounts.new_vesting;
+        new_vesting.beneficiary = ctx.accounts.new_beneficiary.key();
+        new_vesting.sale_state = sale_state.key();
+        new_vesting.total_vect_amount = vesting.total_vect_amount;
+        new_vesting.claimed_amount = vesting.claimed_amount;
+        new_vesting.start_time = vesting.start_time;
+        new_vesting.bump = ctx.bumps.new_vesting;
+        
+        msg!("Transferred vesting of {} VECT ({} unclaimed) to {}",
+            vesting.total_vect_amount,
+            vesting.total_vect_amount - vesting.claimed_amount,
+            new_vesting.beneficiary);
+        
+        Ok(())
+    }
+
+    /// Admin allocates VECT from the vault to a team/advisor grant
+    ///
+    /// Grants carry their own cliff and vesting duration and start vesting
+    /// immediately. They can be revoked later with `revoke_grant`.
+    pub fn create_grant(
+        ctx: Context<CreateGrant>,
+        total_vect_amount: u64,
+        cliff_duration: i64,
+        vesting_duration: i64,
+    ) -> Result<()> {
+        let sale_state = &mut ctx.accounts.sale_state;
+        let clock = Clock::get()?;
+        
+        require!(total_vect_amount > 0, ErrorCode::InvalidAmount);
+        validate_schedule(cliff_duration, vesting_duration)?;
+        
+        // Check vault has enough unreserved tokens
+        sale_state.require_available(ctx.accounts.vect_vault.amount, total_vect_amount)?;
+        
+        let grant = &mut ctx.accounts.grant;
+        grant.beneficiary = ctx.accounts.beneficiary.key();
+        grant.sale_state = sale_state.key();
+        grant.total_vect_amount = total_vect_amount;
+        grant.claimed_amount = 0;
+        grant.start_time = clock.unix_timestamp;
+        grant.cliff_duration = cliff_duration;
+        grant.vesting_duration = vesting_duration;
+        grant.is_revoked = false;
+        grant.bump = ctx.bumps.grant;
+        
+        sale_state.total_vect_granted = sale_state.total_vect_granted
+            .checked_add(total_vect_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        msg!("Granted {} VECT to {} (cliff: {}s, vesting: {}s)",
+            total_vect_amount, grant.beneficiary, cliff_duration, vesting_duration);
+        
+        Ok(())
+    }
+
+    /// Claim vested tokens of a grant
+    pub fn claim_grant(ctx: Context<ClaimGrant>) -> Result<()> {
+        let grant = &mut ctx.accounts.grant;
+        let sale_state = &mut ctx.accounts.sale_state;
+        let clock = Clock::get()?;
+        
+        if !grant.is_revoked {
+            let elapsed = clock.unix_timestamp
+                .checked_sub(grant.start_time)
+                .ok_or(ErrorCode::MathOverflow)?;
+            require!(elapsed >= grant.cliff_duration, ErrorCode::CliffNotReached);
+        }
+        
+        let vested_amount = grant_vested_amount(grant, clock.unix_timestamp)?;
+        let claimable = vested_amount
+            .checked_sub(grant.claimed_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        require!(claimable > 0, ErrorCode::NothingToClaim);
+        
+        // Update claimed amount BEFORE transfer
+        grant.claimed_amount = grant.claimed_amount
+            .checked_add(claimable)
+            .ok_or(ErrorCode::MathOverflow)?;
+        sale_state.total_vect_claimed = sale_state.total_vect_claimed
+            .checked_add(claimable)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        let sale_authority = sale_state.authority;
+        let seeds = &[
+            b"sale",
+            sale_authority.as_ref(),
+            &[sale_state.bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vect_vault.to_account_info(),
+            to: ctx.accounts.beneficiary_vect_account.to_account_info(),
+            authority: ctx.accounts.sale_state.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        
+        token::transfer(cpi_ctx, claimable)?;
+        
+        msg!("Claimed {} VECT from grant (Total claimed: {}/{})",
+            claimable, grant.claimed_amount, grant.total_vect_amount);
+        
+        Ok(())
+    }
+
+    /// Admin revokes a grant
+    ///
+    /// Tokens vested so far stay claimable by the beneficiary; the unvested rest
+    /// is released back to the vault.
+    pub fn revoke_grant(ctx: Context<RevokeGrant>) -> Result<()> {
+        let sale_state = &mut ctx.accounts.sale_state;
+        let grant = &mut ctx.accounts.grant;
+        let clock = Clock::get()?;
+        
+        require!(!grant.is_revoked, ErrorCode::GrantAlreadyRevoked);
+        
+        let vested_amount = grant_vested_amount(grant, clock.unix_timestamp)?;
+        let unvested = grant.total_vect_amount
+            .checked_sub(vested_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        grant.total_vect_amount = vested_amount;
+        grant.is_revoked = true;
+        
+        sale_state.total_vect_granted = sale_state.total_vect_granted
+            .checked_sub(unvested)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        msg!("Grant revoked: {} VECT stay claimable, {} VECT returned to vault",
+            vested_amount, unvested);
+        
+        Ok(())
+    }
+
+    /// Close a fully claimed vesting account and return its rent to the beneficiary
+    pub fn close_vesting(ctx: Context<CloseVesting>) -> Result<()> {
+        let vesting = &ctx.accounts.vesting;
+        require!(
+            vesting.claimed_amount == vesting.total_vect_amount,
+            ErrorCode::VestingNotFullyClaimed
+        );
+        
+        msg!("Closed vesting of {} VECT", vesting.total_vect_amount);
+        Ok(())
+    }
+
+    /// Close a fully claimed position vesting, burning the position NFT and
+    /// returning the rent to the holder
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        let vesting = &ctx.accounts.position_vesting;
+        require!(
+            vesting.claimed_amount == vesting.total_vect_amount,
+            ErrorCode::VestingNotFullyClaimed
+        );
+        
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.position_mint.to_account_info(),
+            from: ctx.accounts.holder_position_account.to_account_info(),
+            authority: ctx.accounts.holder.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        token::burn(CpiContext::new(cpi_program, cpi_accounts), 1)?;
+        
+        msg!("Closed position {} of {} VECT",
+            vesting.position_mint, vesting.total_vect_amount);
+        Ok(())
+    }
+
+    /// Close a fully claimed (or revoked and fully claimed) grant and return
+    /// its rent to the beneficiary
+    pub fn close_grant(ctx: Context<CloseGrant>) -> Result<()> {
+        let grant = &ctx.accounts.grant;
+        require!(
+            grant.claimed_amount == grant.total_vect_amount,
+            ErrorCode::VestingNotFullyClaimed
+        );
+        
+        msg!("Closed grant of {} VECT", grant.total_vect_amount);
+        Ok(())
+    }
+
+    /// Admin closes a settled sale: unsold VECT goes back to the authority and
+    /// the sale state, vault and treasury accounts are closed
+    ///
+    /// Only possible once the sale has ended, all USDC has been withdrawn and
+    /// every sold or granted token has been claimed.
+    pub fn close_sale(ctx: Context<CloseSale>) -> Result<()> {
+        let sale_state = &ctx.accounts.sale_state;
+        
+        require!(sale_state.is_ended, ErrorCode::SaleNotEnded);
+        require!(
+            ctx.accounts.usdc_treasury.amount == 0,
+            ErrorCode::TreasuryNotEmpty
+        );
+        require!(
+            sale_state.outstanding_vect()? == 0,
+            ErrorCode::OutstandingVesting
+        );
+        
+        let seeds = &[
+            b"sale",
+            sale_state.authority.as_ref(),
+            &[sale_state.bump],
+        ];
+        let signer = &[&seeds[..]];
+        
+        // Return unsold VECT to the authority
+        let unsold = ctx.accounts.vect_vault.amount;
+        if unsold > 0 {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.vect_vault.to_account_info(),
+                to: ctx.accounts.authority_vect_account.to_account_info(),
+                authority: ctx.accounts.sale_state.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            
+            token::transfer(cpi_ctx, unsold)?;
+        }
+        
+        for token_account in [
+            ctx.accounts.vect_vault.to_account_info(),
+            ctx.accounts.usdc_treasury.to_account_info(),
+        ] {
+            let cpi_accounts = CloseAccount {
+                account: token_account,
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: ctx.accounts.sale_state.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            
+            token::close_account(cpi_ctx)?;
+        }
+        
+        msg!("Sale closed, {} unsold VECT returned", unsold);
+        Ok(())
+    }
+
     /// Admin withdraws USDC from treasury
     pub fn withdraw_usdc(
         ctx: Context<WithdrawUsdc>,
@@ -284,6 +739,18 @@ pub mod vesting_sale {
         Ok(())
     }
 
+    /// Admin allows or forbids beneficiaries to transfer their vesting
+    pub fn set_vesting_transfers(
+        ctx: Context<UpdateSaleState>,
+        enabled: bool,
+    ) -> Result<()> {
+        let sale_state = &mut ctx.accounts.sale_state;
+        sale_state.vesting_transfers_enabled = enabled;
+        
+        msg!("Vesting transfers {}", if enabled { "enabled" } else { "disabled" });
+        Ok(())
+    }
+
     /// Admin updates the price
     pub fn update_price(
         ctx: Context<UpdateSaleState>,
@@ -317,82 +784,419 @@ fn calculate_vect_amount(usdc_amount: u64, usdc_price_per_vect: u64) -> Result<u
         .checked_div(usdc_price_per_vect as u128)
         .ok_or(ErrorCode::MathOverflow)?;
     
-    u64::try_from(vect_amount).map_err(|_| ErrorCode::MathOverflow.into())
+    u64::try_from(vect_amount).map_err(|_| ErrorCode::MathOverflow.into())
+}
+
+/// Validate cliff and vesting durations against the program limits
+fn validate_schedule(cliff_duration: i64, vesting_duration: i64) -> Result<()> {
+    require!(cliff_duration > 0, ErrorCode::InvalidCliffDuration);
+    require!(cliff_duration <= MAX_CLIFF_DURATION, ErrorCode::InvalidCliffDuration);
+    require!(vesting_duration > 0, ErrorCode::InvalidVestingDuration);
+    require!(vesting_duration <= MAX_VESTING_DURATION, ErrorCode::InvalidVestingDuration);
+    Ok(())
+}
+
+/// Validate a purchase against the sale and record it in the sale statistics
+///
+/// Returns the VECT amount bought; the caller still has to create or update the
+/// vesting and move the USDC.
+fn record_purchase(
+    sale_state: &mut SaleState,
+    vault_balance: u64,
+    usdc_amount: u64,
+) -> Result<u64> {
+    // Check sale status
+    require!(!sale_state.is_paused, ErrorCode::SaleIsPaused);
+    require!(!sale_state.is_ended, ErrorCode::SaleHasEnded);
+    
+    // Validate minimum purchase
+    require!(usdc_amount >= MIN_PURCHASE_USDC, ErrorCode::BelowMinimumPurchase);
+    
+    // Calculate VECT amount with proper decimal handling
+    // Formula: vect_amount = (usdc_amount * 10^VECT_DECIMALS) / usdc_price_per_vect
+    let vect_amount = calculate_vect_amount(
+        usdc_amount,
+        sale_state.usdc_price_per_vect,
+    )?;
+    
+    require!(vect_amount > 0, ErrorCode::InvalidAmount);
+    
+    // Check vault has enough unreserved tokens
+    sale_state.require_available(vault_balance, vect_amount)?;
+    
+    // Update sale statistics
+    sale_state.total_vect_sold = sale_state.total_vect_sold
+        .checked_add(vect_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    sale_state.total_usdc_raised = sale_state.total_usdc_raised
+        .checked_add(usdc_amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+    
+    Ok(vect_amount)
+}
+
+/// Vested amount of a grant; a revoked grant is frozen at its reduced total
+/// (`GrantSchedule` in `vectaiproj-vesting`)
+fn grant_vested_amount(grant: &Grant, now: i64) -> Result<u64> {
+    grant.schedule()
+        .vested_at(now)
+        .ok_or(ErrorCode::MathOverflow.into())
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeSale<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SaleState::INIT_SPACE,
+        seeds = [b"sale", authority.key().as_ref()],
+        bump
+    )]
+    pub sale_state: Account<'info, SaleState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub vect_mint: Account<'info, Mint>,
+    pub usdc_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = authority,
+        token::mint = vect_mint,
+        token::authority = sale_state,
+        seeds = [b"vect_vault", sale_state.key().as_ref()],
+        bump
+    )]
+    pub vect_vault: Account<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = authority,
+        token::mint = usdc_mint,
+        token::authority = sale_state,
+        seeds = [b"usdc_treasury", sale_state.key().as_ref()],
+        bump
+    )]
+    pub usdc_treasury: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AdminFundVault<'info> {
+    #[account(
+        seeds = [b"sale", authority.key().as_ref()],
+        bump = sale_state.bump,
+        has_one = authority,
+    )]
+    pub sale_state: Account<'info, SaleState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub admin_vect_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"vect_vault", sale_state.key().as_ref()],
+        bump,
+    )]
+    pub vect_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyWithUsdc<'info> {
+    #[account(
+        mut,
+        seeds = [b"sale", sale_state.authority.as_ref()],
+        bump = sale_state.bump,
+    )]
+    pub sale_state: Account<'info, SaleState>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = 8 + Vesting::INIT_SPACE,
+        seeds = [b"vesting", sale_state.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub vesting: Account<'info, Vesting>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(mut)]
+    pub buyer_usdc_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"usdc_treasury", sale_state.key().as_ref()],
+        bump,
+    )]
+    pub usdc_treasury: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"vect_vault", sale_state.key().as_ref()],
+        bump,
+    )]
+    pub vect_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyPositionWithUsdc<'info> {
+    #[account(
+        mut,
+        seeds = [b"sale", sale_state.authority.as_ref()],
+        bump = sale_state.bump,
+    )]
+    pub sale_state: Account<'info, SaleState>,
+    
+    #[account(
+        init,
+        payer = buyer,
+        mint::decimals = 0,
+        mint::authority = sale_state,
+    )]
+    pub position_mint: Account<'info, Mint>,
+    
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + Vesting::INIT_SPACE,
+        seeds = [b"position", sale_state.key().as_ref(), position_mint.key().as_ref()],
+        bump
+    )]
+    pub position_vesting: Account<'info, Vesting>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(mut)]
+    pub buyer_usdc_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init,
+        payer = buyer,
+        associated_token::mint = position_mint,
+        associated_token::authority = buyer,
+    )]
+    pub buyer_position_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"usdc_treasury", sale_state.key().as_ref()],
+        bump,
+    )]
+    pub usdc_treasury: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"vect_vault", sale_state.key().as_ref()],
+        bump,
+    )]
+    pub vect_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(
+        mut,
+        seeds = [b"sale", sale_state.authority.as_ref()],
+        bump = sale_state.bump,
+    )]
+    pub sale_state: Account<'info, SaleState>,
+    
+    #[account(
+        mut,
+        seeds = [b"vesting", sale_state.key().as_ref(), beneficiary.key().as_ref()],
+        bump = vesting.bump,
+        has_one = beneficiary,
+    )]
+    pub vesting: Account<'info, Vesting>,
+    
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    
+    pub vect_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = vect_mint,
+        associated_token::authority = beneficiary,
+    )]
+    pub beneficiary_vect_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"vect_vault", sale_state.key().as_ref()],
+        bump,
+    )]
+    pub vect_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPosition<'info> {
+    #[account(
+        mut,
+        seeds = [b"sale", sale_state.authority.as_ref()],
+        bump = sale_state.bump,
+    )]
+    pub sale_state: Account<'info, SaleState>,
+    
+    #[account(
+        mut,
+        seeds = [b"position", sale_state.key().as_ref(), position_mint.key().as_ref()],
+        bump = position_vesting.bump,
+        has_one = position_mint,
+    )]
+    pub position_vesting: Account<'info, Vesting>,
+    
+    pub position_mint: Account<'info, Mint>,
+    
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    
+    #[account(
+        token::mint = position_mint,
+        token::authority = holder,
+        constraint = holder_position_account.amount == 1 @ ErrorCode::NotPositionHolder,
+    )]
+    pub holder_position_account: Account<'info, TokenAccount>,
+    
+    pub vect_mint: Account<'info, Mint>,
+    
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = vect_mint,
+        associated_token::authority = holder,
+    )]
+    pub holder_vect_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"vect_vault", sale_state.key().as_ref()],
+        bump,
+    )]
+    pub vect_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BatchClaim<'info> {
+    #[account(
+        mut,
+        seeds = [b"sale", sale_state.authority.as_ref()],
+        bump = sale_state.bump,
+    )]
+    pub sale_state: Account<'info, SaleState>,
+    
+    #[account(
+        mut,
+        seeds = [b"vect_vault", sale_state.key().as_ref()],
+        bump,
+    )]
+    pub vect_vault: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
 }
 
-// ============================================================================
-// Account Structures
-// ============================================================================
-
 #[derive(Accounts)]
-pub struct InitializeSale<'info> {
+pub struct TransferVesting<'info> {
     #[account(
-        init,
-        payer = authority,
-        space = 8 + SaleState::INIT_SPACE,
-        seeds = [b"sale", authority.key().as_ref()],
-        bump
+        seeds = [b"sale", sale_state.authority.as_ref()],
+        bump = sale_state.bump,
     )]
     pub sale_state: Account<'info, SaleState>,
     
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub vect_mint: Account<'info, Mint>,
-    pub usdc_mint: Account<'info, Mint>,
-    
     #[account(
-        init,
-        payer = authority,
-        token::mint = vect_mint,
-        token::authority = sale_state,
-        seeds = [b"vect_vault", sale_state.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"vesting", sale_state.key().as_ref(), beneficiary.key().as_ref()],
+        bump = vesting.bump,
+        has_one = beneficiary,
+        close = beneficiary,
     )]
-    pub vect_vault: Account<'info, TokenAccount>,
+    pub vesting: Account<'info, Vesting>,
     
     #[account(
         init,
-        payer = authority,
-        token::mint = usdc_mint,
-        token::authority = sale_state,
-        seeds = [b"usdc_treasury", sale_state.key().as_ref()],
+        payer = beneficiary,
+        space = 8 + Vesting::INIT_SPACE,
+        seeds = [b"vesting", sale_state.key().as_ref(), new_beneficiary.key().as_ref()],
         bump
     )]
-    pub usdc_treasury: Account<'info, TokenAccount>,
+    pub new_vesting: Account<'info, Vesting>,
+    
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    
+    /// CHECK: Any wallet may receive a vesting; only used as seed and beneficiary
+    pub new_beneficiary: UncheckedAccount<'info>,
     
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct AdminFundVault<'info> {
+pub struct CreateGrant<'info> {
     #[account(
+        mut,
         seeds = [b"sale", authority.key().as_ref()],
         bump = sale_state.bump,
         has_one = authority,
     )]
     pub sale_state: Account<'info, SaleState>,
     
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Grant::INIT_SPACE,
+        seeds = [b"grant", sale_state.key().as_ref(), beneficiary.key().as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, Grant>,
+    
+    /// CHECK: Any wallet may receive a grant; only used as seed and beneficiary
+    pub beneficiary: UncheckedAccount<'info>,
     
     #[account(mut)]
-    pub admin_vect_account: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
     
     #[account(
-        mut,
         seeds = [b"vect_vault", sale_state.key().as_ref()],
         bump,
     )]
     pub vect_vault: Account<'info, TokenAccount>,
     
-    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct BuyWithUsdc<'info> {
+pub struct ClaimGrant<'info> {
     #[account(
         mut,
         seeds = [b"sale", sale_state.authority.as_ref()],
@@ -401,26 +1205,25 @@ pub struct BuyWithUsdc<'info> {
     pub sale_state: Account<'info, SaleState>,
     
     #[account(
-        init_if_needed,
-        payer = buyer,
-        space = 8 + Vesting::INIT_SPACE,
-        seeds = [b"vesting", sale_state.key().as_ref(), buyer.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"grant", sale_state.key().as_ref(), beneficiary.key().as_ref()],
+        bump = grant.bump,
+        has_one = beneficiary,
     )]
-    pub vesting: Account<'info, Vesting>,
+    pub grant: Account<'info, Grant>,
     
     #[account(mut)]
-    pub buyer: Signer<'info>,
+    pub beneficiary: Signer<'info>,
     
-    #[account(mut)]
-    pub buyer_usdc_account: Account<'info, TokenAccount>,
+    pub vect_mint: Account<'info, Mint>,
     
     #[account(
-        mut,
-        seeds = [b"usdc_treasury", sale_state.key().as_ref()],
-        bump,
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = vect_mint,
+        associated_token::authority = beneficiary,
     )]
-    pub usdc_treasury: Account<'info, TokenAccount>,
+    pub beneficiary_vect_account: Account<'info, TokenAccount>,
     
     #[account(
         mut,
@@ -430,37 +1233,111 @@ pub struct BuyWithUsdc<'info> {
     pub vect_vault: Account<'info, TokenAccount>,
     
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Claim<'info> {
+pub struct RevokeGrant<'info> {
     #[account(
-        seeds = [b"sale", sale_state.authority.as_ref()],
+        mut,
+        seeds = [b"sale", authority.key().as_ref()],
         bump = sale_state.bump,
+        has_one = authority,
     )]
     pub sale_state: Account<'info, SaleState>,
     
     #[account(
         mut,
-        seeds = [b"vesting", sale_state.key().as_ref(), beneficiary.key().as_ref()],
+        seeds = [b"grant", sale_state.key().as_ref(), grant.beneficiary.as_ref()],
+        bump = grant.bump,
+    )]
+    pub grant: Account<'info, Grant>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVesting<'info> {
+    // Seeds use the stored sale so this still works after `close_sale`
+    #[account(
+        mut,
+        seeds = [b"vesting", vesting.sale_state.as_ref(), beneficiary.key().as_ref()],
         bump = vesting.bump,
         has_one = beneficiary,
+        close = beneficiary,
     )]
     pub vesting: Account<'info, Vesting>,
     
     #[account(mut)]
     pub beneficiary: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    // Seeds use the stored sale so this still works after `close_sale`
+    #[account(
+        mut,
+        seeds = [b"position", position_vesting.sale_state.as_ref(), position_mint.key().as_ref()],
+        bump = position_vesting.bump,
+        has_one = position_mint,
+        close = holder,
+    )]
+    pub position_vesting: Account<'info, Vesting>,
     
-    pub vect_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub position_mint: Account<'info, Mint>,
+    
+    #[account(mut)]
+    pub holder: Signer<'info>,
     
     #[account(
-        init_if_needed,
-        payer = beneficiary,
-        associated_token::mint = vect_mint,
-        associated_token::authority = beneficiary,
+        mut,
+        token::mint = position_mint,
+        token::authority = holder,
+        constraint = holder_position_account.amount == 1 @ ErrorCode::NotPositionHolder,
     )]
-    pub beneficiary_vect_account: Account<'info, TokenAccount>,
+    pub holder_position_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGrant<'info> {
+    // Seeds use the stored sale so this still works after `close_sale`
+    #[account(
+        mut,
+        seeds = [b"grant", grant.sale_state.as_ref(), beneficiary.key().as_ref()],
+        bump = grant.bump,
+        has_one = beneficiary,
+        close = beneficiary,
+    )]
+    pub grant: Account<'info, Grant>,
+    
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSale<'info> {
+    #[account(
+        mut,
+        seeds = [b"sale", authority.key().as_ref()],
+        bump = sale_state.bump,
+        has_one = authority,
+        close = authority,
+    )]
+    pub sale_state: Account<'info, SaleState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = sale_state.vect_mint,
+    )]
+    pub authority_vect_account: Account<'info, TokenAccount>,
     
     #[account(
         mut,
@@ -469,9 +1346,14 @@ pub struct Claim<'info> {
     )]
     pub vect_vault: Account<'info, TokenAccount>,
     
+    #[account(
+        mut,
+        seeds = [b"usdc_treasury", sale_state.key().as_ref()],
+        bump,
+    )]
+    pub usdc_treasury: Account<'info, TokenAccount>,
+    
     pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -537,21 +1419,104 @@ pub struct SaleState {
     pub is_ended: bool,
     
     pub bump: u8,
+    
+    // Appended after `bump` so existing fields keep their offsets
+    pub vesting_transfers_enabled: bool,
+    pub total_vect_granted: u64,
+    pub total_vect_claimed: u64,
+}
+
+impl SaleState {
+    /// VECT sold or granted that has not been claimed yet
+    pub fn outstanding_vect(&self) -> Result<u64> {
+        self.total_vect_sold
+            .checked_add(self.total_vect_granted)
+            .and_then(|committed| committed.checked_sub(self.total_vect_claimed))
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+    
+    /// Check the vault holds `amount` VECT on top of everything already
+    /// outstanding, so a new purchase or grant cannot spend tokens owed to
+    /// earlier buyers and grantees
+    pub fn require_available(&self, vault_balance: u64, amount: u64) -> Result<()> {
+        let required = self.outstanding_vect()?
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(vault_balance >= required, ErrorCode::InsufficientVaultBalance);
+        Ok(())
+    }
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct Vesting {
+    /// Wallet allowed to claim; `Pubkey::default()` for position NFTs
+    pub beneficiary: Pubkey,
+    pub sale_state: Pubkey,
+    
+    pub total_vect_amount: u64,
+    pub claimed_amount: u64,
+    pub start_time: i64,
+    
+    pub bump: u8,
+    
+    // Appended after `bump` so existing fields keep their offsets
+    /// Mint of the position NFT whose holder may claim, if any
+    pub position_mint: Pubkey,
+}
+
+impl Vesting {
+    /// Unlock schedule of this position under the sale's cliff and duration
+    pub fn schedule(&self, sale_state: &SaleState) -> Schedule {
+        Schedule {
+            total_amount: self.total_vect_amount,
+            start_time: self.start_time,
+            cliff_duration: sale_state.cliff_duration,
+            vesting_duration: sale_state.vesting_duration,
+        }
+    }
+    
+    /// VECT a claim at `now` pays out: vested under the sale's schedule minus
+    /// what was already claimed
+    pub fn claimable(&self, sale_state: &SaleState, now: i64) -> Result<u64> {
+        self.schedule(sale_state)
+            .claimable_at(self.claimed_amount, now)
+            .ok_or(ErrorCode::MathOverflow.into())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Grant {
     pub beneficiary: Pubkey,
     pub sale_state: Pubkey,
     
     pub total_vect_amount: u64,
     pub claimed_amount: u64,
     pub start_time: i64,
+    pub cliff_duration: i64,
+    pub vesting_duration: i64,
+    
+    pub is_revoked: bool,
     
     pub bump: u8,
 }
 
+impl Grant {
+    /// Unlock schedule of this grant under its own cliff and duration
+    pub fn schedule(&self) -> GrantSchedule {
+        GrantSchedule {
+            schedule: Schedule {
+                total_amount: self.total_vect_amount,
+                start_time: self.start_time,
+                cliff_duration: self.cliff_duration,
+                vesting_duration: self.vesting_duration,
+            },
+            is_revoked: self.is_revoked,
+        }
+    }
+}
+
 // ============================================================================
 // Error Codes
 // ============================================================================
@@ -602,4 +1567,341 @@ pub enum ErrorCode {
     
     #[msg("Purchase amount below minimum (10 USDC)")]
     BelowMinimumPurchase,
+    
+    #[msg("Batch accounts must be non-empty (Vesting, token account) pairs")]
+    InvalidBatchAccounts,
+    
+    #[msg("Too many vesting accounts in one batch")]
+    BatchTooLarge,
+    
+    #[msg("Vesting account does not belong to this sale")]
+    InvalidVestingAccount,
+    
+    #[msg("Token account is not the beneficiary's VECT account")]
+    InvalidBeneficiaryAccount,
+    
+    #[msg("Vesting transfers are disabled for this sale")]
+    VestingTransfersDisabled,
+    
+    #[msg("New beneficiary must differ from the current one")]
+    InvalidNewBeneficiary,
+    
+    #[msg("Vesting has no unclaimed tokens to transfer")]
+    NothingToTransfer,
+    
+    #[msg("Signer does not hold the position NFT")]
+    NotPositionHolder,
+    
+    #[msg("Grant has already been revoked")]
+    GrantAlreadyRevoked,
+    
+    #[msg("Vesting still has unclaimed tokens")]
+    VestingNotFullyClaimed,
+    
+    #[msg("Sale has not ended")]
+    SaleNotEnded,
+    
+    #[msg("USDC treasury must be withdrawn first")]
+    TreasuryNotEmpty,
+    
+    #[msg("Sold or granted VECT is still waiting to be claimed")]
+    OutstandingVesting,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 60 * 60;
+    const CLIFF: i64 = 90 * DAY;
+    const VESTING: i64 = 365 * DAY;
+    const START: i64 = 1_700_000_000;
+    const PRICE: u64 = 50_000; // 0.05 USDC
+    const TOTAL: u64 = 2_000_000_000; // 100 USDC at PRICE
+
+    fn sale_state() -> SaleState {
+        SaleState {
+            authority: Pubkey::default(),
+            vect_mint: Pubkey::default(),
+            usdc_mint: Pubkey::default(),
+            vect_vault: Pubkey::default(),
+            usdc_treasury: Pubkey::default(),
+            cliff_duration: CLIFF,
+            vesting_duration: VESTING,
+            usdc_price_per_vect: PRICE,
+            total_vect_sold: 0,
+            total_usdc_raised: 0,
+            total_vect_granted: 0,
+            total_vect_claimed: 0,
+            is_paused: false,
+            is_ended: false,
+            vesting_transfers_enabled: false,
+            bump: 0,
+        }
+    }
+
+    /// What `claim` vests for an allocation, through `vectaiproj-vesting`
+    fn calculate_vested_amount(
+        total_vect_amount: u64,
+        start_time: i64,
+        cliff_duration: i64,
+        vesting_duration: i64,
+        now: i64,
+    ) -> Result<u64> {
+        Schedule {
+            total_amount: total_vect_amount,
+            start_time,
+            cliff_duration,
+            vesting_duration,
+        }
+        .vested_at(now)
+        .ok_or(ErrorCode::MathOverflow.into())
+    }
+
+    fn vested_at(now: i64) -> u64 {
+        calculate_vested_amount(TOTAL, START, CLIFF, VESTING, now).unwrap()
+    }
+
+    #[test]
+    fn purchase_amount_uses_vect_decimals() {
+        assert_eq!(calculate_vect_amount(100_000_000, PRICE).unwrap(), TOTAL);
+        // Example from the doc comment: 10 USDC at 0.2 USDC.
+        assert_eq!(calculate_vect_amount(10_000_000, 200_000).unwrap(), 50_000_000);
+        // Rounds down.
+        assert_eq!(calculate_vect_amount(10_000_001, 3).unwrap(), 3_333_333_666_666);
+        assert!(calculate_vect_amount(u64::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn record_purchase_checks_the_sale() {
+        let mut sale = sale_state();
+        assert_eq!(record_purchase(&mut sale, TOTAL, 100_000_000).unwrap(), TOTAL);
+        assert_eq!(sale.total_vect_sold, TOTAL);
+        assert_eq!(sale.total_usdc_raised, 100_000_000);
+
+        assert_eq!(
+            record_purchase(&mut sale, TOTAL, MIN_PURCHASE_USDC - 1).unwrap_err(),
+            ErrorCode::BelowMinimumPurchase.into()
+        );
+        assert_eq!(
+            record_purchase(&mut sale, TOTAL - 1, 100_000_000).unwrap_err(),
+            ErrorCode::InsufficientVaultBalance.into()
+        );
+        sale.is_paused = true;
+        assert_eq!(
+            record_purchase(&mut sale, TOTAL, 100_000_000).unwrap_err(),
+            ErrorCode::SaleIsPaused.into()
+        );
+        sale.is_paused = false;
+        sale.is_ended = true;
+        assert_eq!(
+            record_purchase(&mut sale, TOTAL, 100_000_000).unwrap_err(),
+            ErrorCode::SaleHasEnded.into()
+        );
+        // Failed purchases leave the totals alone.
+        assert_eq!(sale.total_vect_sold, TOTAL);
+    }
+
+    #[test]
+    fn vesting_unlocks_linearly_after_the_cliff() {
+        assert_eq!(vested_at(START), 0);
+        assert_eq!(vested_at(START + CLIFF - 1), 0);
+        assert_eq!(vested_at(START + CLIFF), 0);
+        // 2000 VECT * 86_400 / 31_536_000, rounded down.
+        assert_eq!(vested_at(START + CLIFF + DAY), 5_479_452);
+        assert_eq!(vested_at(START + CLIFF + VESTING / 4), TOTAL / 4);
+        assert_eq!(vested_at(START + CLIFF + VESTING / 2), TOTAL / 2);
+        assert_eq!(vested_at(START + CLIFF + VESTING - 1), 1_999_999_936);
+        assert_eq!(vested_at(START + CLIFF + VESTING), TOTAL);
+        assert_eq!(vested_at(i64::MAX), TOTAL);
+        // A clock before the start underflows nothing; it is simply pre-cliff.
+        assert_eq!(vested_at(START - 1), 0);
+    }
+
+    #[test]
+    fn weekly_claims_add_up_to_the_total() {
+        let mut claimed = 0u64;
+        let mut now = START;
+        while now <= START + CLIFF + VESTING + 7 * DAY {
+            let vested = vested_at(now);
+            assert!(vested >= claimed, "vested amount went backwards at {now}");
+            claimed = vested;
+            now += 7 * DAY;
+        }
+        assert_eq!(claimed, TOTAL);
+    }
+
+    #[test]
+    fn revoked_grant_freezes_at_the_vested_amount() {
+        let mut grant = Grant {
+            beneficiary: Pubkey::default(),
+            sale_state: Pubkey::default(),
+            total_vect_amount: 1_000_000_000,
+            claimed_amount: 0,
+            start_time: START,
+            cliff_duration: 30 * DAY,
+            vesting_duration: 100 * DAY,
+            is_revoked: false,
+            bump: 0,
+        };
+        assert_eq!(grant_vested_amount(&grant, START + 55 * DAY).unwrap(), 250_000_000);
+
+        // What `revoke_grant` does halfway through.
+        let vested = grant_vested_amount(&grant, START + 80 * DAY).unwrap();
+        assert_eq!(vested, 500_000_000);
+        grant.total_vect_amount = vested;
+        grant.is_revoked = true;
+        assert_eq!(grant_vested_amount(&grant, START + 1_000 * DAY).unwrap(), 500_000_000);
+    }
+
+    #[test]
+    fn outstanding_vect_tracks_claims() {
+        let mut sale = sale_state();
+        sale.total_vect_sold = TOTAL;
+        sale.total_vect_granted = 500;
+        sale.total_vect_claimed = 1_500;
+        assert_eq!(sale.outstanding_vect().unwrap(), TOTAL + 500 - 1_500);
+        sale.total_vect_claimed = TOTAL + 501;
+        assert!(sale.outstanding_vect().is_err());
+    }
+
+    #[test]
+    fn new_commitments_need_unreserved_vault_tokens() {
+        let mut sale = sale_state();
+        sale.total_vect_sold = TOTAL;
+        sale.total_vect_granted = 500;
+        sale.total_vect_claimed = 1_500;
+        let outstanding = TOTAL + 500 - 1_500;
+
+        sale.require_available(outstanding + 100, 100).unwrap();
+        assert_eq!(
+            sale.require_available(outstanding + 99, 100).unwrap_err(),
+            ErrorCode::InsufficientVaultBalance.into()
+        );
+        assert!(sale.require_available(u64::MAX, u64::MAX).is_err());
+
+        // A purchase the vault could cover on its own is refused while the
+        // tokens are owed to earlier buyers.
+        assert_eq!(
+            record_purchase(&mut sale, outstanding + TOTAL - 1, 100_000_000).unwrap_err(),
+            ErrorCode::InsufficientVaultBalance.into()
+        );
+        assert_eq!(record_purchase(&mut sale, outstanding + TOTAL, 100_000_000).unwrap(), TOTAL);
+    }
+
+    mod props {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Any schedule `initialize` would accept
+        fn schedule() -> impl Strategy<Value = (i64, i64)> {
+            (1..=MAX_CLIFF_DURATION, 1..=MAX_VESTING_DURATION)
+        }
+
+        fn vesting(total_vect_amount: u64, start_time: i64) -> Vesting {
+            Vesting {
+                beneficiary: Pubkey::default(),
+                sale_state: Pubkey::default(),
+                position_mint: Pubkey::default(),
+                total_vect_amount,
+                claimed_amount: 0,
+                start_time,
+                bump: 0,
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn vested_is_monotonic_in_time(
+                total in any::<u64>(),
+                start in 0..i64::MAX / 2,
+                (cliff, duration) in schedule(),
+                a in 0..i64::MAX / 2,
+                b in 0..i64::MAX / 2,
+            ) {
+                let (earlier, later) = (a.min(b), a.max(b));
+                let vested = |now| calculate_vested_amount(total, start, cliff, duration, now);
+                prop_assert!(vested(earlier)? <= vested(later)?);
+            }
+
+            #[test]
+            fn vested_never_exceeds_the_total(
+                total in any::<u64>(),
+                start in 0..i64::MAX / 2,
+                (cliff, duration) in schedule(),
+                now in 0..i64::MAX / 2,
+            ) {
+                let vested = calculate_vested_amount(total, start, cliff, duration, now)?;
+                prop_assert!(vested <= total);
+                if now - start >= cliff + duration {
+                    prop_assert_eq!(vested, total);
+                }
+            }
+
+            #[test]
+            fn extreme_inputs_error_instead_of_panicking(
+                total in any::<u64>(),
+                start in any::<i64>(),
+                cliff in any::<i64>(),
+                duration in any::<i64>(),
+                now in any::<i64>(),
+                usdc in any::<u64>(),
+                price in any::<u64>(),
+            ) {
+                let _ = calculate_vested_amount(total, start, cliff, duration, now);
+                let _ = calculate_vect_amount(usdc, price);
+                prop_assert!(calculate_vect_amount(usdc, 0).is_err());
+            }
+
+            #[test]
+            fn partial_claims_sum_to_a_single_claim(
+                total in any::<u64>(),
+                start in 0..i64::MAX / 4,
+                (cliff, duration) in schedule(),
+                mut offsets in prop::collection::vec(0..2 * (MAX_CLIFF_DURATION + MAX_VESTING_DURATION), 1..32),
+            ) {
+                let mut sale = sale_state();
+                sale.cliff_duration = cliff;
+                sale.vesting_duration = duration;
+                offsets.sort_unstable();
+                let last = start + offsets[offsets.len() - 1];
+
+                let mut partial = vesting(total, start);
+                let mut paid = 0u64;
+                for offset in offsets {
+                    let claimable = partial.claimable(&sale, start + offset)?;
+                    partial.claimed_amount += claimable;
+                    paid += claimable;
+                }
+
+                let single = vesting(total, start).claimable(&sale, last)?;
+                prop_assert_eq!(paid, single);
+            }
+
+            #[test]
+            fn purchase_rounds_down(
+                usdc in any::<u64>(),
+                price in 1..=u64::MAX,
+            ) {
+                let Ok(vect) = calculate_vect_amount(usdc, price) else {
+                    // Only a result too large for u64 may fail.
+                    prop_assert!(usdc as u128 * 1_000_000 / price as u128 > u64::MAX as u128);
+                    return Ok(());
+                };
+                let paid = usdc as u128 * 1_000_000;
+                prop_assert!(vect as u128 * price as u128 <= paid);
+                prop_assert!(paid < (vect as u128 + 1) * price as u128);
+            }
+
+            #[test]
+            fn purchase_is_monotonic_in_usdc(
+                a in 0..u64::MAX / 1_000_000,
+                b in 0..u64::MAX / 1_000_000,
+                price in 1..=u64::MAX,
+            ) {
+                let (less, more) = (a.min(b), a.max(b));
+                prop_assert!(calculate_vect_amount(less, price)? <= calculate_vect_amount(more, price)?);
+            }
+        }
+    }
 }
@@ -0,0 +1,544 @@
+//! Instruction-level tests: the SBF build of the program from `anchor build`
+//! running in litesvm, with the clock warped instead of waited for.
+//!
+//! They load `target/deploy/vectaiproj.so`, so they are `#[ignore]`d and run
+//! with `scripts/build-programs.sh vectaiproj && cargo test -p vectaiproj
+//! --test litesvm -- --ignored`; a missing build fails them.
+
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use anchor_spl::token::spl_token;
+use litesvm::LiteSVM;
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+use vectaiproj::ErrorCode;
+use vectaiproj_client::{instructions, pda, Grant, SaleState, Vesting};
+
+const PROGRAM: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy/vectaiproj.so");
+
+const DAY: i64 = 24 * 60 * 60;
+const CLIFF: i64 = 90 * DAY;
+const VESTING: i64 = 365 * DAY;
+const START: i64 = 1_700_000_000;
+const PRICE: u64 = 50_000; // 0.05 USDC
+const FUND: u64 = 1_000_000_000_000; // 1M VECT
+const PURCHASE_USDC: u64 = 100_000_000; // 100 USDC
+const PURCHASE_VECT: u64 = 2_000_000_000; // 2000 VECT at PRICE
+
+/// An initialized and funded sale in a fresh VM, the clock at `START`.
+struct Sale {
+    svm: LiteSVM,
+    /// Pays every transaction fee, so signers' lamports only move by what
+    /// the program does.
+    payer: Keypair,
+    authority: Keypair,
+    vect_mint: Pubkey,
+    usdc_mint: Pubkey,
+    sale: Pubkey,
+}
+
+impl Sale {
+    fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(vectaiproj::ID, PROGRAM).unwrap_or_else(|err| {
+            panic!("{PROGRAM}: {err}, run `scripts/build-programs.sh vectaiproj` first")
+        });
+
+        let (payer, authority) = (Keypair::new(), Keypair::new());
+        svm.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+        svm.airdrop(&authority.pubkey(), 100_000_000_000).unwrap();
+        let mut sale = Sale {
+            svm,
+            payer,
+            vect_mint: Pubkey::new_unique(),
+            usdc_mint: Pubkey::new_unique(),
+            sale: pda::sale(&authority.pubkey()).0,
+            authority,
+        };
+        sale.create_mint(sale.vect_mint);
+        sale.create_mint(sale.usdc_mint);
+        sale.warp_to(START);
+
+        let authority = sale.authority.pubkey();
+        let ix = instructions::initialize_sale(
+            authority,
+            sale.vect_mint,
+            sale.usdc_mint,
+            CLIFF,
+            VESTING,
+            PRICE,
+        );
+        sale.admin(ix).unwrap();
+        let funding = sale.token_account(authority, sale.vect_mint, FUND);
+        sale.admin(instructions::admin_fund_vault(authority, funding, FUND)).unwrap();
+        sale
+    }
+
+    fn create_mint(&mut self, mint: Pubkey) {
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        let state = spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply: u64::MAX,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        spl_token::state::Mint::pack(state, &mut data).unwrap();
+        self.set_token_program_account(mint, data);
+    }
+
+    /// A token account for `owner` holding `amount`, at a fresh address.
+    fn token_account(&mut self, owner: Pubkey, mint: Pubkey, amount: u64) -> Pubkey {
+        let address = Pubkey::new_unique();
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        let state = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        spl_token::state::Account::pack(state, &mut data).unwrap();
+        self.set_token_program_account(address, data);
+        address
+    }
+
+    fn set_token_program_account(&mut self, address: Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: self.svm.minimum_balance_for_rent_exemption(data.len()),
+            data,
+            owner: spl_token::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.svm.set_account(address, account).unwrap();
+    }
+
+    /// A funded wallet and its USDC account holding `usdc`.
+    fn wallet(&mut self, usdc: u64) -> (Keypair, Pubkey) {
+        let wallet = Keypair::new();
+        self.svm.airdrop(&wallet.pubkey(), 10_000_000_000).unwrap();
+        let usdc_account = self.token_account(wallet.pubkey(), self.usdc_mint, usdc);
+        (wallet, usdc_account)
+    }
+
+    fn warp_to(&mut self, unix_timestamp: i64) {
+        let mut clock: Clock = self.svm.get_sysvar();
+        clock.unix_timestamp = unix_timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// Send `ixs` signed by `signers`, the fee paid by `payer`; the error is
+    /// the program's.
+    fn send(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> Result<(), TransactionError> {
+        // Identical retries would otherwise be rejected as already processed
+        self.svm.expire_blockhash();
+        let mut all = vec![&self.payer];
+        all.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            ixs,
+            Some(&self.payer.pubkey()),
+            &all,
+            self.svm.latest_blockhash(),
+        );
+        self.svm.send_transaction(tx).map(|_| ()).map_err(|failed| failed.err)
+    }
+
+    fn admin(&mut self, ix: Instruction) -> Result<(), TransactionError> {
+        let authority = self.authority.insecure_clone();
+        self.send(&[ix], &[&authority])
+    }
+
+    /// Lamports of `address`, 0 once it is closed.
+    fn lamports(&self, address: &Pubkey) -> u64 {
+        self.svm.get_account(address).map_or(0, |account| account.lamports)
+    }
+
+    fn balance(&self, account: &Pubkey) -> u64 {
+        let account = self.svm.get_account(account).expect("token account exists");
+        spl_token::state::Account::unpack(&account.data).unwrap().amount
+    }
+
+    fn vect_balance(&self, owner: &Pubkey) -> u64 {
+        self.balance(&get_associated_token_address(owner, &self.vect_mint))
+    }
+
+    fn state(&self) -> SaleState {
+        vectaiproj_client::sale_state(&self.svm.get_account(&self.sale).unwrap().data).unwrap()
+    }
+
+    fn vesting(&self, address: &Pubkey) -> Vesting {
+        vectaiproj_client::vesting(&self.svm.get_account(address).unwrap().data).unwrap()
+    }
+
+    fn grant(&self, beneficiary: &Pubkey) -> Grant {
+        let address = pda::grant(&self.sale, beneficiary).0;
+        vectaiproj_client::grant(&self.svm.get_account(&address).unwrap().data).unwrap()
+    }
+
+    fn supply(&self, mint: &Pubkey) -> u64 {
+        let account = self.svm.get_account(mint).expect("mint exists");
+        spl_token::state::Mint::unpack(&account.data).unwrap().supply
+    }
+
+    /// Run `ix`, signed by `signer`, and return how many lamports `signer`
+    /// gained from it (the rent of the accounts it closed).
+    fn refund(&mut self, ix: Instruction, signer: &Keypair) -> u64 {
+        let before = self.lamports(&signer.pubkey());
+        self.send(&[ix], &[signer]).unwrap();
+        self.lamports(&signer.pubkey()) - before
+    }
+}
+
+/// The error a failed single-instruction transaction reports for `code`.
+fn program_error(code: ErrorCode) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(code.into()))
+}
+
+#[test]
+#[ignore = "needs target/deploy/vectaiproj.so"]
+fn purchases_vest_and_claim_exactly() {
+    let mut sale = Sale::new();
+    let s = sale.sale;
+
+    let (buyer, buyer_usdc) = sale.wallet(PURCHASE_USDC);
+    let (cranked, cranked_usdc) = sale.wallet(PURCHASE_USDC);
+    let (holder, holder_usdc) = sale.wallet(PURCHASE_USDC);
+    let position_mint = Keypair::new();
+
+    let ix = instructions::buy_with_usdc(s, buyer.pubkey(), buyer_usdc, PURCHASE_USDC);
+    sale.send(&[ix], &[&buyer]).unwrap();
+    let ix = instructions::buy_with_usdc(s, cranked.pubkey(), cranked_usdc, PURCHASE_USDC);
+    sale.send(&[ix], &[&cranked]).unwrap();
+    let ix = instructions::buy_position_with_usdc(
+        s,
+        holder.pubkey(),
+        position_mint.pubkey(),
+        holder_usdc,
+        PURCHASE_USDC,
+    );
+    sale.send(&[ix], &[&holder, &position_mint]).unwrap();
+
+    let state = sale.state();
+    assert_eq!(state.total_vect_sold, 3 * PURCHASE_VECT);
+    assert_eq!(state.total_usdc_raised, 3 * PURCHASE_USDC);
+    assert_eq!(sale.balance(&pda::usdc_treasury(&s).0), 3 * PURCHASE_USDC);
+
+    // One second before the cliff nothing can be claimed
+    sale.warp_to(START + CLIFF - 1);
+    let claim = instructions::claim(s, buyer.pubkey(), sale.vect_mint);
+    assert_eq!(
+        sale.send(std::slice::from_ref(&claim), &[&buyer]),
+        Err(program_error(ErrorCode::CliffNotReached))
+    );
+
+    // Halfway through the linear phase exactly half has vested
+    sale.warp_to(START + CLIFF + VESTING / 2);
+    sale.send(std::slice::from_ref(&claim), &[&buyer]).unwrap();
+    assert_eq!(sale.vect_balance(&buyer.pubkey()), PURCHASE_VECT / 2);
+
+    let cranked_vect = get_associated_token_address(&cranked.pubkey(), &sale.vect_mint);
+    // batch_claim pays into existing accounts only, so the crank creates it
+    let crank = [
+        create_associated_token_account_idempotent(
+            &sale.authority.pubkey(),
+            &cranked.pubkey(),
+            &sale.vect_mint,
+            &spl_token::ID,
+        ),
+        instructions::batch_claim(s, &[(cranked.pubkey(), cranked_vect)]),
+    ];
+    let cranker = sale.authority.insecure_clone();
+    sale.send(&crank, &[&cranker]).unwrap();
+    assert_eq!(sale.balance(&cranked_vect), PURCHASE_VECT / 2);
+
+    let claim_position =
+        instructions::claim_position(s, holder.pubkey(), position_mint.pubkey(), sale.vect_mint);
+    sale.send(std::slice::from_ref(&claim_position), &[&holder]).unwrap();
+    assert_eq!(sale.vect_balance(&holder.pubkey()), PURCHASE_VECT / 2);
+
+    // Claiming again at the same time pays nothing
+    assert_eq!(
+        sale.send(std::slice::from_ref(&claim), &[&buyer]),
+        Err(program_error(ErrorCode::NothingToClaim))
+    );
+
+    // After the schedule the rest is paid, without rounding dust
+    sale.warp_to(START + CLIFF + VESTING);
+    sale.send(&[claim], &[&buyer]).unwrap();
+    sale.send(&crank[1..], &[&cranker]).unwrap();
+    sale.send(&[claim_position], &[&holder]).unwrap();
+    assert_eq!(sale.vect_balance(&buyer.pubkey()), PURCHASE_VECT);
+    assert_eq!(sale.balance(&cranked_vect), PURCHASE_VECT);
+    assert_eq!(sale.vect_balance(&holder.pubkey()), PURCHASE_VECT);
+
+    let state = sale.state();
+    assert_eq!(state.total_vect_claimed, 3 * PURCHASE_VECT);
+    assert_eq!(sale.balance(&pda::vect_vault(&s).0), FUND - 3 * PURCHASE_VECT);
+}
+
+#[test]
+#[ignore = "needs target/deploy/vectaiproj.so"]
+fn grants_vest_on_their_own_schedule() {
+    let mut sale = Sale::new();
+    let (member, _) = sale.wallet(0);
+    let amount = 1_000_000_000; // 1000 VECT
+
+    let ix = instructions::create_grant(
+        sale.authority.pubkey(),
+        member.pubkey(),
+        amount,
+        30 * DAY,
+        100 * DAY,
+    );
+    sale.admin(ix).unwrap();
+    let claim = instructions::claim_grant(sale.sale, member.pubkey(), sale.vect_mint);
+
+    // 25 of the 100 linear days after a 30 day cliff
+    sale.warp_to(START + 55 * DAY);
+    sale.send(std::slice::from_ref(&claim), &[&member]).unwrap();
+    assert_eq!(sale.vect_balance(&member.pubkey()), amount / 4);
+
+    sale.warp_to(START + 130 * DAY);
+    sale.send(&[claim], &[&member]).unwrap();
+    assert_eq!(sale.vect_balance(&member.pubkey()), amount);
+    assert_eq!(sale.state().total_vect_claimed, amount);
+}
+
+#[test]
+#[ignore = "needs target/deploy/vectaiproj.so"]
+fn admin_controls_reject_with_their_error_codes() {
+    let mut sale = Sale::new();
+    let authority = sale.authority.pubkey();
+    let (buyer, buyer_usdc) = sale.wallet(3 * PURCHASE_USDC);
+    let buy = instructions::buy_with_usdc(sale.sale, buyer.pubkey(), buyer_usdc, PURCHASE_USDC);
+
+    sale.admin(instructions::pause_sale(authority)).unwrap();
+    assert_eq!(sale.send(std::slice::from_ref(&buy), &[&buyer]), Err(program_error(ErrorCode::SaleIsPaused)));
+    assert_eq!(
+        sale.admin(instructions::pause_sale(authority)),
+        Err(program_error(ErrorCode::SaleAlreadyPaused))
+    );
+    sale.admin(instructions::unpause_sale(authority)).unwrap();
+    sale.send(std::slice::from_ref(&buy), &[&buyer]).unwrap();
+
+    sale.admin(instructions::end_sale(authority)).unwrap();
+    assert_eq!(sale.send(&[buy], &[&buyer]), Err(program_error(ErrorCode::SaleHasEnded)));
+    assert_eq!(
+        sale.admin(instructions::pause_sale(authority)),
+        Err(program_error(ErrorCode::SaleHasEnded))
+    );
+    assert_eq!(
+        sale.admin(instructions::end_sale(authority)),
+        Err(program_error(ErrorCode::SaleAlreadyEnded))
+    );
+
+    let to = sale.token_account(authority, sale.usdc_mint, 0);
+    assert_eq!(
+        sale.admin(instructions::withdraw_usdc(authority, to, 0)),
+        Err(program_error(ErrorCode::InvalidAmount))
+    );
+    sale.admin(instructions::withdraw_usdc(authority, to, PURCHASE_USDC)).unwrap();
+    assert_eq!(sale.balance(&to), PURCHASE_USDC);
+    assert_eq!(sale.balance(&pda::usdc_treasury(&sale.sale).0), 0);
+
+    // Someone else's sale PDA does not exist, so they cannot withdraw
+    let (outsider, _) = sale.wallet(0);
+    let ix = instructions::withdraw_usdc(outsider.pubkey(), to, 1);
+    assert!(sale.send(&[ix], &[&outsider]).is_err());
+}
+
+#[test]
+#[ignore = "needs target/deploy/vectaiproj.so"]
+fn vesting_transfers_keep_the_schedule() {
+    let mut sale = Sale::new();
+    let (s, authority) = (sale.sale, sale.authority.pubkey());
+    let (buyer, buyer_usdc) = sale.wallet(PURCHASE_USDC);
+    let (heir, _) = sale.wallet(0);
+    let ix = instructions::buy_with_usdc(s, buyer.pubkey(), buyer_usdc, PURCHASE_USDC);
+    sale.send(&[ix], &[&buyer]).unwrap();
+
+    let transfer = instructions::transfer_vesting(s, buyer.pubkey(), heir.pubkey());
+    assert_eq!(
+        sale.send(std::slice::from_ref(&transfer), &[&buyer]),
+        Err(program_error(ErrorCode::VestingTransfersDisabled))
+    );
+    sale.admin(instructions::set_vesting_transfers(authority, true)).unwrap();
+    assert!(sale.state().vesting_transfers_enabled);
+    let to_self = instructions::transfer_vesting(s, buyer.pubkey(), buyer.pubkey());
+    assert!(sale.send(&[to_self], &[&buyer]).is_err());
+
+    sale.warp_to(START + CLIFF + VESTING / 2);
+    sale.send(&[instructions::claim(s, buyer.pubkey(), sale.vect_mint)], &[&buyer]).unwrap();
+    let old = pda::vesting(&s, &buyer.pubkey()).0;
+    let old_rent = sale.lamports(&old);
+    // The buyer pays the new account's rent and gets the old one's back
+    assert_eq!(sale.refund(transfer, &buyer), 0);
+    assert_eq!(old_rent, sale.lamports(&pda::vesting(&s, &heir.pubkey()).0));
+    assert_eq!(sale.lamports(&old), 0);
+
+    let moved = sale.vesting(&pda::vesting(&s, &heir.pubkey()).0);
+    assert_eq!(moved.beneficiary, heir.pubkey());
+    assert_eq!(moved.total_vect_amount, PURCHASE_VECT);
+    assert_eq!(moved.claimed_amount, PURCHASE_VECT / 2);
+    assert_eq!(moved.start_time, START);
+
+    // The heir only gets what the buyer had not claimed, on the same schedule
+    let claim = instructions::claim(s, heir.pubkey(), sale.vect_mint);
+    assert_eq!(
+        sale.send(std::slice::from_ref(&claim), &[&heir]),
+        Err(program_error(ErrorCode::NothingToClaim))
+    );
+    sale.warp_to(START + CLIFF + VESTING);
+    sale.send(&[claim], &[&heir]).unwrap();
+    assert_eq!(sale.vect_balance(&heir.pubkey()), PURCHASE_VECT / 2);
+    assert_eq!(sale.vect_balance(&buyer.pubkey()), PURCHASE_VECT / 2);
+
+    sale.admin(instructions::set_vesting_transfers(authority, false)).unwrap();
+    let back = instructions::transfer_vesting(s, heir.pubkey(), buyer.pubkey());
+    assert_eq!(
+        sale.send(&[back], &[&heir]),
+        Err(program_error(ErrorCode::VestingTransfersDisabled))
+    );
+}
+
+#[test]
+#[ignore = "needs target/deploy/vectaiproj.so"]
+fn revoked_grants_pay_what_had_vested_and_close() {
+    let mut sale = Sale::new();
+    let (s, authority) = (sale.sale, sale.authority.pubkey());
+    let (member, _) = sale.wallet(0);
+    let amount = 1_000_000_000; // 1000 VECT
+
+    let ix = instructions::create_grant(authority, member.pubkey(), amount, 30 * DAY, 100 * DAY);
+    sale.admin(ix).unwrap();
+    assert_eq!(sale.state().total_vect_granted, amount);
+
+    // Revoked 25 of the 100 linear days after the cliff
+    sale.warp_to(START + 55 * DAY);
+    sale.admin(instructions::revoke_grant(authority, member.pubkey())).unwrap();
+    let grant = sale.grant(&member.pubkey());
+    assert!(grant.is_revoked);
+    assert_eq!(grant.total_vect_amount, amount / 4);
+    assert_eq!(sale.state().total_vect_granted, amount / 4);
+    assert_eq!(
+        sale.admin(instructions::revoke_grant(authority, member.pubkey())),
+        Err(program_error(ErrorCode::GrantAlreadyRevoked))
+    );
+
+    let close = instructions::close_grant(s, member.pubkey());
+    assert_eq!(
+        sale.send(std::slice::from_ref(&close), &[&member]),
+        Err(program_error(ErrorCode::VestingNotFullyClaimed))
+    );
+
+    // Nothing more vests after the revocation
+    sale.warp_to(START + 130 * DAY);
+    sale.send(&[instructions::claim_grant(s, member.pubkey(), sale.vect_mint)], &[&member]).unwrap();
+    assert_eq!(sale.vect_balance(&member.pubkey()), amount / 4);
+    assert_eq!(sale.state().total_vect_claimed, amount / 4);
+
+    let grant = pda::grant(&s, &member.pubkey()).0;
+    let rent = sale.lamports(&grant);
+    assert_eq!(sale.refund(close, &member), rent);
+    assert_eq!(sale.lamports(&grant), 0);
+}
+
+#[test]
+#[ignore = "needs target/deploy/vectaiproj.so"]
+fn update_price_applies_to_later_purchases() {
+    let mut sale = Sale::new();
+    let authority = sale.authority.pubkey();
+    let (buyer, buyer_usdc) = sale.wallet(2 * PURCHASE_USDC);
+    let buy = instructions::buy_with_usdc(sale.sale, buyer.pubkey(), buyer_usdc, PURCHASE_USDC);
+    sale.send(std::slice::from_ref(&buy), &[&buyer]).unwrap();
+
+    assert_eq!(
+        sale.admin(instructions::update_price(authority, 0)),
+        Err(program_error(ErrorCode::InvalidPrice))
+    );
+    sale.admin(instructions::update_price(authority, 2 * PRICE)).unwrap();
+    assert_eq!(sale.state().usdc_price_per_vect, 2 * PRICE);
+
+    // Same USDC, half the VECT, added to the same vesting
+    sale.send(&[buy], &[&buyer]).unwrap();
+    let vesting = sale.vesting(&pda::vesting(&sale.sale, &buyer.pubkey()).0);
+    assert_eq!(vesting.total_vect_amount, PURCHASE_VECT + PURCHASE_VECT / 2);
+    assert_eq!(sale.state().total_vect_sold, PURCHASE_VECT + PURCHASE_VECT / 2);
+    assert_eq!(sale.state().total_usdc_raised, 2 * PURCHASE_USDC);
+}
+
+#[test]
+#[ignore = "needs target/deploy/vectaiproj.so"]
+fn settled_accounts_close_and_return_their_rent() {
+    let mut sale = Sale::new();
+    let (s, authority) = (sale.sale, sale.authority.pubkey());
+    let (buyer, buyer_usdc) = sale.wallet(PURCHASE_USDC);
+    let (holder, holder_usdc) = sale.wallet(PURCHASE_USDC);
+    let position_mint = Keypair::new();
+    let ix = instructions::buy_with_usdc(s, buyer.pubkey(), buyer_usdc, PURCHASE_USDC);
+    sale.send(&[ix], &[&buyer]).unwrap();
+    let ix = instructions::buy_position_with_usdc(
+        s,
+        holder.pubkey(),
+        position_mint.pubkey(),
+        holder_usdc,
+        PURCHASE_USDC,
+    );
+    sale.send(&[ix], &[&holder, &position_mint]).unwrap();
+
+    let vesting = pda::vesting(&s, &buyer.pubkey()).0;
+    let position = pda::position(&s, &position_mint.pubkey()).0;
+    let close_vesting = instructions::close_vesting(s, buyer.pubkey());
+    let close_position = instructions::close_position(s, holder.pubkey(), position_mint.pubkey());
+    assert_eq!(
+        sale.send(std::slice::from_ref(&close_vesting), &[&buyer]),
+        Err(program_error(ErrorCode::VestingNotFullyClaimed))
+    );
+    assert_eq!(
+        sale.send(std::slice::from_ref(&close_position), &[&holder]),
+        Err(program_error(ErrorCode::VestingNotFullyClaimed))
+    );
+
+    // The sale only closes once ended, withdrawn and fully claimed
+    let authority_vect = sale.token_account(authority, sale.vect_mint, 0);
+    let close_sale = instructions::close_sale(authority, authority_vect);
+    assert_eq!(sale.admin(close_sale.clone()), Err(program_error(ErrorCode::SaleNotEnded)));
+    sale.admin(instructions::end_sale(authority)).unwrap();
+    assert_eq!(sale.admin(close_sale.clone()), Err(program_error(ErrorCode::TreasuryNotEmpty)));
+    let to = sale.token_account(authority, sale.usdc_mint, 0);
+    sale.admin(instructions::withdraw_usdc(authority, to, 2 * PURCHASE_USDC)).unwrap();
+    assert_eq!(sale.admin(close_sale.clone()), Err(program_error(ErrorCode::OutstandingVesting)));
+
+    sale.warp_to(START + CLIFF + VESTING);
+    sale.send(&[instructions::claim(s, buyer.pubkey(), sale.vect_mint)], &[&buyer]).unwrap();
+    let claim_position =
+        instructions::claim_position(s, holder.pubkey(), position_mint.pubkey(), sale.vect_mint);
+    sale.send(&[claim_position], &[&holder]).unwrap();
+
+    let (vault, treasury) = (pda::vect_vault(&s).0, pda::usdc_treasury(&s).0);
+    let sale_rent = sale.lamports(&s) + sale.lamports(&vault) + sale.lamports(&treasury);
+    assert_eq!(sale.refund(close_sale, &sale.authority.insecure_clone()), sale_rent);
+    assert_eq!(sale.balance(&authority_vect), FUND - 2 * PURCHASE_VECT);
+    for closed in [s, vault, treasury] {
+        assert_eq!(sale.lamports(&closed), 0);
+    }
+
+    // Vestings and positions still close after the sale, by their stored sale
+    let rent = sale.lamports(&vesting);
+    assert_eq!(sale.refund(close_vesting, &buyer), rent);
+    assert_eq!(sale.lamports(&vesting), 0);
+
+    let rent = sale.lamports(&position);
+    assert_eq!(sale.refund(close_position, &holder), rent);
+    assert_eq!(sale.lamports(&position), 0);
+    assert_eq!(sale.supply(&position_mint.pubkey()), 0);
+    let nft = get_associated_token_address(&holder.pubkey(), &position_mint.pubkey());
+    assert_eq!(sale.balance(&nft), 0);
+}
@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+# Build the SBF programs into target/deploy, as `anchor build` does, for the
+# litesvm tests and the parity_swap fuzz target. Needs the Solana CLI
+# (`cargo build-sbf`), not the Anchor CLI.
+#
+#   scripts/build-programs.sh                          # every program
+#   scripts/build-programs.sh mock_swap parity_swap    # just these
+set -euo pipefail
+cd "$(dirname "$0")/.."
+
+programs=("$@")
+if [[ ${#programs[@]} -eq 0 ]]; then
+  programs=(vectaiproj parity_swap mock_swap)
+fi
+
+for program in "${programs[@]}"; do
+  cargo build-sbf --manifest-path "programs/$program/Cargo.toml"
+done
@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+# Build crates/vectaiproj-vesting for the browser.
+#
+# The crate is an rlib only, so the program and clients link it as usual;
+# the cdylib that wasm-bindgen needs is requested here with `cargo rustc`.
+#
+#   scripts/build-vesting-wasm.sh          # compile check (CI)
+#   scripts/build-vesting-wasm.sh pkg      # also emit JS bindings into crates/vectaiproj-vesting/pkg
+set -euo pipefail
+cd "$(dirname "$0")/.."
+
+cargo rustc -p vectaiproj-vesting --lib --release \
+  --target wasm32-unknown-unknown --features wasm --crate-type cdylib
+
+if [[ "${1:-}" == "pkg" ]]; then
+  wasm-bindgen --target web --out-dir crates/vectaiproj-vesting/pkg \
+    target/wasm32-unknown-unknown/release/vectaiproj_vesting.wasm
+fi
@@ -0,0 +1,345 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import {
+  TOKEN_PROGRAM_ID,
+  createAccount,
+  createMint,
+  getAccount,
+  getMint,
+  mintTo,
+} from "@solana/spl-token";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import BN from "bn.js";
+import { assert } from "chai";
+
+describe("mock_swap", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.MockSwap as Program;
+  const authority = provider.wallet as anchor.Wallet;
+  const payer = authority.payer;
+
+  const DECIMALS = 6;
+  const FEE_BPS = 30n;
+  const ONE = 1_000_000n; // 1 token with 6 decimals
+
+  let mintA: PublicKey;
+  let mintB: PublicKey;
+  let pool: PublicKey;
+  let vaultA: PublicKey;
+  let vaultB: PublicKey;
+  let lpMint: PublicKey;
+  let userA: PublicKey;
+  let userB: PublicKey;
+  let userLp: PublicKey;
+
+  const balance = async (account: PublicKey) =>
+    (await getAccount(provider.connection, account)).amount;
+
+  const liquidityAccounts = () => ({
+    user: authority.publicKey,
+    pool,
+    vaultA,
+    vaultB,
+    lpMint,
+    userA,
+    userB,
+    userLp,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  const swapIx = (
+    from: PublicKey,
+    to: PublicKey,
+    amountIn: bigint,
+    minOut: bigint
+  ) =>
+    program.methods
+      .swap(new BN(amountIn.toString()), new BN(minOut.toString()))
+      .accounts({
+        authority: authority.publicKey,
+        pool,
+        userSource: from,
+        userDestination: to,
+        vaultA,
+        vaultB,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      });
+
+  before(async () => {
+    mintA = await createMint(
+      provider.connection,
+      payer,
+      authority.publicKey,
+      null,
+      DECIMALS
+    );
+    mintB = await createMint(
+      provider.connection,
+      payer,
+      authority.publicKey,
+      null,
+      DECIMALS
+    );
+
+    [pool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool"), mintA.toBuffer(), mintB.toBuffer()],
+      program.programId
+    );
+    [vaultA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), pool.toBuffer(), mintA.toBuffer()],
+      program.programId
+    );
+    [vaultB] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), pool.toBuffer(), mintB.toBuffer()],
+      program.programId
+    );
+    [lpMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("lp_mint"), pool.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePool(Number(FEE_BPS))
+      .accounts({
+        payer: authority.publicKey,
+        mintA,
+        mintB,
+        pool,
+        vaultA,
+        vaultB,
+        lpMint,
+      })
+      .rpc();
+
+    userA = await createAccount(
+      provider.connection,
+      payer,
+      mintA,
+      authority.publicKey,
+      Keypair.generate()
+    );
+    userB = await createAccount(
+      provider.connection,
+      payer,
+      mintB,
+      authority.publicKey,
+      Keypair.generate()
+    );
+    userLp = await createAccount(
+      provider.connection,
+      payer,
+      lpMint,
+      authority.publicKey,
+      Keypair.generate()
+    );
+    await mintTo(
+      provider.connection,
+      payer,
+      mintA,
+      userA,
+      authority.publicKey,
+      1_000n * ONE
+    );
+    await mintTo(
+      provider.connection,
+      payer,
+      mintB,
+      userB,
+      authority.publicKey,
+      1_000n * ONE
+    );
+  });
+
+  it("mints sqrt(a * b) LP for the first deposit", async () => {
+    await program.methods
+      .addLiquidity(
+        new BN((400n * ONE).toString()),
+        new BN((100n * ONE).toString()),
+        new BN(0)
+      )
+      .accounts(liquidityAccounts())
+      .rpc();
+
+    assert.equal(await balance(vaultA), 400n * ONE);
+    assert.equal(await balance(vaultB), 100n * ONE);
+    assert.equal(await balance(userLp), 200n * ONE);
+  });
+
+  it("mints pro rata for later deposits", async () => {
+    await program.methods
+      .addLiquidity(
+        new BN((40n * ONE).toString()),
+        new BN((10n * ONE).toString()),
+        new BN(0)
+      )
+      .accounts(liquidityAccounts())
+      .rpc();
+
+    assert.equal(await balance(userLp), 220n * ONE);
+  });
+
+  it("swaps along x * y = k with the pool fee", async () => {
+    const amountIn = 10n * ONE;
+    const reserveIn = await balance(vaultA);
+    const reserveOut = await balance(vaultB);
+    const inAfterFee = (amountIn * (10_000n - FEE_BPS)) / 10_000n;
+    const expectedOut = (inAfterFee * reserveOut) / (reserveIn + inAfterFee);
+
+    try {
+      await swapIx(userA, userB, amountIn, expectedOut + 1n).rpc();
+      assert.fail("Swap below min output should fail");
+    } catch (e) {
+      assert.include(e.toString(), "SlippageExceeded");
+    }
+
+    const userBBefore = await balance(userB);
+    await swapIx(userA, userB, amountIn, expectedOut).rpc();
+
+    assert.equal((await balance(userB)) - userBBefore, expectedOut);
+    assert.equal(await balance(vaultA), reserveIn + amountIn);
+    assert.equal(await balance(vaultB), reserveOut - expectedOut);
+    assert.isTrue(
+      (reserveIn + amountIn) * (reserveOut - expectedOut) >=
+        reserveIn * reserveOut,
+      "k must not decrease"
+    );
+  });
+
+  it("accepts Raydium AMM v4 swap_base_in instructions", async () => {
+    const amountIn = 5n * ONE;
+    const reserveIn = await balance(vaultB);
+    const reserveOut = await balance(vaultA);
+    const inAfterFee = (amountIn * (10_000n - FEE_BPS)) / 10_000n;
+    const expectedOut = (inAfterFee * reserveOut) / (reserveIn + inAfterFee);
+
+    // Same bytes and account order Raydium client code produces.
+    const data = Buffer.alloc(17);
+    data.writeUInt8(9, 0);
+    data.writeBigUInt64LE(amountIn, 1);
+    data.writeBigUInt64LE(expectedOut, 9);
+    const unused = () => ({
+      pubkey: Keypair.generate().publicKey,
+      isSigner: false,
+      isWritable: false,
+    });
+    const writable = (pubkey: PublicKey) => ({
+      pubkey,
+      isSigner: false,
+      isWritable: true,
+    });
+    const ix = new anchor.web3.TransactionInstruction({
+      programId: program.programId,
+      keys: [
+        { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        writable(pool), // amm
+        unused(), // amm_authority
+        unused(), // amm_open_orders
+        unused(), // amm_target_orders
+        writable(vaultA), // pool coin vault
+        writable(vaultB), // pool pc vault
+        ...Array.from({ length: 8 }, unused), // market program .. vault signer
+        writable(userB),
+        writable(userA),
+        { pubkey: authority.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    });
+
+    const userABefore = await balance(userA);
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(ix));
+    assert.equal((await balance(userA)) - userABefore, expectedOut);
+  });
+
+  it("injects failures from the pool's failure config", async () => {
+    const [failureConfig] = PublicKey.findProgramAddressSync(
+      [Buffer.from("failure"), pool.toBuffer()],
+      program.programId
+    );
+    // Only the pool's creator may set up failure injection.
+    const outsider = Keypair.generate();
+    try {
+      await program.methods
+        .initializeFailureConfig()
+        .accounts({ authority: outsider.publicKey, pool, failureConfig })
+        .signers([outsider])
+        .rpc();
+      assert.fail("Only the pool creator should create the failure config");
+    } catch (e) {
+      assert.include(e.toString(), "ConstraintHasOne");
+    }
+    await program.methods
+      .initializeFailureConfig()
+      .accounts({ authority: authority.publicKey, pool, failureConfig })
+      .rpc();
+    const setMode = (mode: object) =>
+      program.methods
+        .setFailureMode(mode)
+        .accounts({ authority: authority.publicKey, failureConfig })
+        .rpc();
+    const configAccount = [
+      { pubkey: failureConfig, isSigner: false, isWritable: false },
+    ];
+
+    try {
+      await setMode({ error: { code: 42 } });
+      try {
+        await swapIx(userA, userB, ONE, 0n)
+          .remainingAccounts(configAccount)
+          .rpc();
+        assert.fail("Injected error should fail the swap");
+      } catch (e) {
+        assert.include(e.toString(), "0x2a");
+      }
+
+      // Short output ignores min_amount_out and pays half the quote.
+      const amountIn = ONE;
+      const inAfterFee = (amountIn * (10_000n - FEE_BPS)) / 10_000n;
+      const reserveIn = await balance(vaultA);
+      const reserveOut = await balance(vaultB);
+      const expectedOut = (inAfterFee * reserveOut) / (reserveIn + inAfterFee);
+      await setMode({ shortOutput: { outputBps: 5_000 } });
+      const userBBefore = await balance(userB);
+      await swapIx(userA, userB, amountIn, expectedOut)
+        .remainingAccounts(configAccount)
+        .rpc();
+      assert.equal((await balance(userB)) - userBBefore, expectedOut / 2n);
+    } finally {
+      await setMode({ disabled: {} });
+    }
+  });
+
+  it("rejects accounts outside the pool mints", async () => {
+    try {
+      await swapIx(userA, userA, ONE, 0n).rpc();
+      assert.fail("Same-mint swap should fail");
+    } catch (e) {
+      assert.include(e.toString(), "InvalidMint");
+    }
+  });
+
+  it("returns a pro-rata share on withdrawal", async () => {
+    const lpAmount = 22n * ONE;
+    const supply = (await getMint(provider.connection, lpMint)).supply;
+    const reserveA = await balance(vaultA);
+    const reserveB = await balance(vaultB);
+    const userABefore = await balance(userA);
+    const userBBefore = await balance(userB);
+
+    await program.methods
+      .removeLiquidity(new BN(lpAmount.toString()), new BN(0), new BN(0))
+      .accounts(liquidityAccounts())
+      .rpc();
+
+    assert.equal(
+      (await balance(userA)) - userABefore,
+      (reserveA * lpAmount) / supply
+    );
+    assert.equal(
+      (await balance(userB)) - userBBefore,
+      (reserveB * lpAmount) / supply
+    );
+    assert.equal(await balance(userLp), 198n * ONE);
+  });
+});
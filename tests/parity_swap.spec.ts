@@ -25,13 +25,26 @@ describe("parity_swap", () => {
 
   const DECIMALS = 6;
   const BUY_AMOUNT = 1_000_000n; // 1 token with 6 decimals
+  const POOL_LIQUIDITY = 1_000n * BUY_AMOUNT;
+  const FEE_BPS = 30n;
 
   let mintA: anchor.web3.PublicKey;
   let mintB: anchor.web3.PublicKey;
+  // The user holds token A in `userSource` and token B in `userDestination`;
+  // buying swaps A for B, selling swaps B for A.
   let userSource: anchor.web3.PublicKey;
   let userDestination: anchor.web3.PublicKey;
-  let poolSource: anchor.web3.PublicKey;
-  let poolDestination: anchor.web3.PublicKey;
+  let ammPool: anchor.web3.PublicKey;
+  let ammVaultA: anchor.web3.PublicKey;
+  let ammVaultB: anchor.web3.PublicKey;
+
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+    "BPFLoaderUpgradeab1e11111111111111111111111"
+  );
 
   const toRemainingAccounts = (
     keys: anchor.web3.AccountMeta[]
@@ -45,19 +58,57 @@ describe("parity_swap", () => {
   const buildMockSwapIx = async (
     from: PublicKey,
     to: PublicKey,
-    amount: bigint
+    amount: bigint,
+    swapAuthority: PublicKey = authority.publicKey
   ): Promise<anchor.web3.TransactionInstruction> => {
     return mockProgram.methods
-      .swap(new BN(amount.toString()))
+      .swap(new BN(amount.toString()), new BN(0))
       .accounts({
-        authority: authority.publicKey,
-        from,
-        to,
+        authority: swapAuthority,
+        pool: ammPool,
+        userSource: from,
+        userDestination: to,
+        vaultA: ammVaultA,
+        vaultB: ammVaultB,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
       .instruction();
   };
 
+  // mock_swap swap accounts are
+  // [authority, pool, user_source, user_destination, vault_a, vault_b, token_program].
+  const MOCK_DESTINATION_INDEX = 3;
+
+  const balance = async (account: PublicKey) =>
+    (await getAccount(provider.connection, account)).amount;
+
+  // Constant-product output of the mock pool at its current reserves.
+  const quote = async (amountIn: bigint, aToB: boolean) => {
+    const reserveA = await balance(ammVaultA);
+    const reserveB = await balance(ammVaultB);
+    const [reserveIn, reserveOut] = aToB
+      ? [reserveA, reserveB]
+      : [reserveB, reserveA];
+    const inAfterFee = (amountIn * (10_000n - FEE_BPS)) / 10_000n;
+    return (inAfterFee * reserveOut) / (reserveIn + inAfterFee);
+  };
+
+  const toRoute = (
+    ix: anchor.web3.TransactionInstruction,
+    minAmountOut: bigint
+  ) => ({
+    ixData: Buffer.from(ix.data),
+    accountsLen: ix.keys.length,
+    destinationIndex: MOCK_DESTINATION_INDEX,
+    minAmountOut: new BN(minAmountOut.toString()),
+  });
+
+  const swapAccounts = (dexProgram: PublicKey = MOCK_SWAP_PROGRAM_ID) => ({
+    authority: authority.publicKey,
+    config: configPda,
+    dexProgram,
+  });
+
   const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
 
   const primeBlockhash = async (retries = 5) => {
@@ -83,6 +134,34 @@ describe("parity_swap", () => {
   before(async () => {
     await primeBlockhash();
 
+    // The upgrade authority (provider wallet on localnet) owns the allowlist.
+    const existingConfig = await provider.connection.getAccountInfo(configPda);
+    if (!existingConfig) {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+      );
+      await program.methods
+        .initializeConfig()
+        .accounts({
+          config: configPda,
+          admin: authority.publicKey,
+          program: program.programId,
+          programData,
+        })
+        .rpc();
+    }
+    const config = await program.account.routerConfig.fetch(configPda);
+    const allowed = (config.allowedPrograms as PublicKey[]).map((p) =>
+      p.toBase58()
+    );
+    if (!allowed.includes(MOCK_SWAP_PROGRAM_ID.toBase58())) {
+      await program.methods
+        .addAllowedProgram(MOCK_SWAP_PROGRAM_ID)
+        .accounts({ config: configPda, admin: authority.publicKey })
+        .rpc();
+    }
+
     mintA = await createMint(
       provider.connection,
       payer,
@@ -115,53 +194,92 @@ describe("parity_swap", () => {
       Keypair.generate()
     );
 
-    await primeBlockhash();
-    poolSource = await createAccount(
+    await mintTo(
       provider.connection,
       payer,
       mintA,
+      userSource,
       authority.publicKey,
-      Keypair.generate()
+      10n * POOL_LIQUIDITY
     );
-    poolDestination = await createAccount(
+    await mintTo(
       provider.connection,
       payer,
       mintB,
+      userDestination,
       authority.publicKey,
-      Keypair.generate()
+      10n * POOL_LIQUIDITY
     );
 
-    // User starts with token A; pool starts with token B for buy path.
-    await mintTo(
-      provider.connection,
-      payer,
-      mintA,
-      userSource,
-      authority.publicKey,
-      10_000_000n
+    // Seed a mock x*y=k pool as the counterparty of every swap.
+    [ammPool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pool"), mintA.toBuffer(), mintB.toBuffer()],
+      MOCK_SWAP_PROGRAM_ID
     );
-    await mintTo(
+    [ammVaultA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), ammPool.toBuffer(), mintA.toBuffer()],
+      MOCK_SWAP_PROGRAM_ID
+    );
+    [ammVaultB] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), ammPool.toBuffer(), mintB.toBuffer()],
+      MOCK_SWAP_PROGRAM_ID
+    );
+    const [lpMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("lp_mint"), ammPool.toBuffer()],
+      MOCK_SWAP_PROGRAM_ID
+    );
+    await mockProgram.methods
+      .initializePool(Number(FEE_BPS))
+      .accounts({
+        payer: authority.publicKey,
+        mintA,
+        mintB,
+        pool: ammPool,
+        vaultA: ammVaultA,
+        vaultB: ammVaultB,
+        lpMint,
+      })
+      .rpc();
+    const userLp = await createAccount(
       provider.connection,
       payer,
-      mintB,
-      poolDestination,
+      lpMint,
       authority.publicKey,
-      10_000_000n
+      Keypair.generate()
     );
+    await mockProgram.methods
+      .addLiquidity(
+        new BN(POOL_LIQUIDITY.toString()),
+        new BN(POOL_LIQUIDITY.toString()),
+        new BN(0)
+      )
+      .accounts({
+        user: authority.publicKey,
+        pool: ammPool,
+        vaultA: ammVaultA,
+        vaultB: ammVaultB,
+        lpMint,
+        userA: userSource,
+        userB: userDestination,
+        userLp,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
   });
 
   it("routes to buy path when sum is odd", async () => {
     const buyIx = await buildMockSwapIx(
-      poolDestination,
+      userSource,
       userDestination,
       BUY_AMOUNT
     );
     const sellIx = await buildMockSwapIx(
+      userDestination,
       userSource,
-      poolSource,
       BUY_AMOUNT
     );
 
+    const expectedOut = await quote(BUY_AMOUNT, true);
     const destinationBefore = (await getAccount(
       provider.connection,
       userDestination
@@ -170,17 +288,13 @@ describe("parity_swap", () => {
     await program.methods
       .executeParitySwap(
         [new BN(1), new BN(2)], // sum = 3 (odd)
-        Buffer.from(buyIx.data),
-        Buffer.from(sellIx.data),
-        buyIx.keys.length
+        { parity: {} },
+        [toRoute(sellIx, expectedOut), toRoute(buyIx, expectedOut)]
       )
-      .accounts({
-        authority: authority.publicKey,
-        dexProgram: MOCK_SWAP_PROGRAM_ID,
-      })
+      .accounts(swapAccounts())
       .remainingAccounts([
-        ...toRemainingAccounts(buyIx.keys),
         ...toRemainingAccounts(sellIx.keys),
+        ...toRemainingAccounts(buyIx.keys),
       ])
       .rpc();
 
@@ -191,68 +305,1014 @@ describe("parity_swap", () => {
 
     assert.equal(
       destinationAfter - destinationBefore,
-      BUY_AMOUNT,
+      expectedOut,
       "Buy branch should credit user destination"
     );
   });
 
   it("routes to sell path when sum is even", async () => {
     const buyIx = await buildMockSwapIx(
-      poolDestination,
+      userSource,
+      userDestination,
+      BUY_AMOUNT
+    );
+    const sellIx = await buildMockSwapIx(
+      userDestination,
+      userSource,
+      BUY_AMOUNT
+    );
+
+    const expectedOut = await quote(BUY_AMOUNT, false);
+    const userSourceBefore = await balance(userSource);
+    const userDestinationBefore = await balance(userDestination);
+
+    await program.methods
+      .executeParitySwap(
+        [new BN(2), new BN(2)], // sum = 4 (even)
+        { parity: {} },
+        [toRoute(sellIx, expectedOut), toRoute(buyIx, expectedOut)]
+      )
+      .accounts(swapAccounts())
+      .remainingAccounts([
+        ...toRemainingAccounts(sellIx.keys),
+        ...toRemainingAccounts(buyIx.keys),
+      ])
+      .rpc();
+
+    assert.equal(
+      userDestinationBefore - (await balance(userDestination)),
+      BUY_AMOUNT,
+      "Sell branch should spend token B"
+    );
+    assert.equal(
+      (await balance(userSource)) - userSourceBefore,
+      expectedOut,
+      "Sell branch should credit token A"
+    );
+  });
+
+  it("threshold strategy buys once the sum reaches the threshold", async () => {
+    const buyIx = await buildMockSwapIx(
+      userSource,
       userDestination,
       BUY_AMOUNT
     );
     const sellIx = await buildMockSwapIx(
+      userDestination,
       userSource,
-      poolSource,
       BUY_AMOUNT
     );
 
-    const userSourceBefore = (await getAccount(
+    const expectedOut = await quote(BUY_AMOUNT, true);
+    const destinationBefore = (await getAccount(
       provider.connection,
-      userSource
+      userDestination
     )).amount;
-    const poolSourceBefore = (await getAccount(
+
+    // Even sum would sell under parity; threshold 10 <= 10 buys instead.
+    await program.methods
+      .executeParitySwap(
+        [new BN(4), new BN(6)],
+        { threshold: { threshold: new BN(10) } },
+        [toRoute(sellIx, 0n), toRoute(buyIx, expectedOut)]
+      )
+      .accounts(swapAccounts())
+      .remainingAccounts([
+        ...toRemainingAccounts(sellIx.keys),
+        ...toRemainingAccounts(buyIx.keys),
+      ])
+      .rpc();
+
+    const destinationAfter = (await getAccount(
       provider.connection,
-      poolSource
+      userDestination
     )).amount;
+    assert.equal(destinationAfter - destinationBefore, expectedOut);
+  });
 
+  it("modulo strategy selects among three routes", async () => {
+    const amounts = [1n, 2n, 3n].map((n) => n * 100_000n);
+    const routeIxs = await Promise.all(
+      amounts.map((amount) =>
+        buildMockSwapIx(userSource, userDestination, amount)
+      )
+    );
+
+    const expectedOut = await quote(amounts[2], true);
+    const destinationBefore = (await getAccount(
+      provider.connection,
+      userDestination
+    )).amount;
+
+    // 5 mod 3 = 2 -> third route
     await program.methods
       .executeParitySwap(
-        [new BN(2), new BN(2)], // sum = 4 (even)
-        Buffer.from(buyIx.data),
-        Buffer.from(sellIx.data),
-        buyIx.keys.length
+        [new BN(5)],
+        { modulo: {} },
+        routeIxs.map((ix) => toRoute(ix, 0n))
       )
-      .accounts({
-        authority: authority.publicKey,
-        dexProgram: MOCK_SWAP_PROGRAM_ID,
-      })
+      .accounts(swapAccounts())
+      .remainingAccounts(routeIxs.flatMap((ix) => toRemainingAccounts(ix.keys)))
+      .rpc();
+
+    const destinationAfter = (await getAccount(
+      provider.connection,
+      userDestination
+    )).amount;
+    assert.equal(destinationAfter - destinationBefore, expectedOut);
+  });
+
+  it("reports the fill in SwapDecisionV2", async () => {
+    const buyIx = await buildMockSwapIx(
+      userSource,
+      userDestination,
+      BUY_AMOUNT
+    );
+    const sellIx = await buildMockSwapIx(
+      userDestination,
+      userSource,
+      BUY_AMOUNT
+    );
+
+    const expectedOut = await quote(BUY_AMOUNT, true);
+    const destinationBefore = (await getAccount(
+      provider.connection,
+      userDestination
+    )).amount;
+
+    const signature = await program.methods
+      .executeParitySwap(
+        [new BN(2), new BN(5)], // sum = 7 (odd)
+        { parity: {} },
+        [toRoute(sellIx, 0n), toRoute(buyIx, 0n)]
+      )
+      .accounts(swapAccounts())
       .remainingAccounts([
+        ...toRemainingAccounts(sellIx.keys),
         ...toRemainingAccounts(buyIx.keys),
+      ])
+      .rpc({ commitment: "confirmed" });
+
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    const event = [...parser.parseLogs(tx.meta.logMessages)].find(
+      (e) => e.name === "swapDecisionV2"
+    );
+    assert.ok(event, "SwapDecisionV2 should be emitted");
+
+    const { data } = event;
+    assert.equal(data.version, 2);
+    assert.isTrue(data.authority.equals(authority.publicKey));
+    assert.isTrue(data.dexProgram.equals(MOCK_SWAP_PROGRAM_ID));
+    assert.equal(data.inputCount, 2);
+    assert.equal(data.sum.toNumber(), 7);
+    assert.equal(data.routeIndex, 1);
+    assert.equal(data.routeCount, 2);
+    assert.deepEqual(
+      data.fill.ixDataHash,
+      [...createHash("sha256").update(buyIx.data).digest()]
+    );
+    assert.isTrue(data.fill.outputAccount.equals(userDestination));
+    assert.equal(data.fill.balanceBefore.toString(), destinationBefore.toString());
+    assert.equal(
+      data.fill.balanceAfter.toString(),
+      (destinationBefore + expectedOut).toString()
+    );
+  });
+
+  it("previews the decision without swapping", async () => {
+    const buyIx = await buildMockSwapIx(
+      userSource,
+      userDestination,
+      BUY_AMOUNT
+    );
+    const sellIx = await buildMockSwapIx(
+      userDestination,
+      userSource,
+      BUY_AMOUNT
+    );
+
+    const destinationBefore = (await getAccount(
+      provider.connection,
+      userDestination
+    )).amount;
+
+    const preview = await program.methods
+      .previewParitySwap(
+        [new BN(1), new BN(2)], // sum = 3 (odd)
+        { parity: {} },
+        [toRoute(sellIx, 0n), toRoute(buyIx, 0n)]
+      )
+      .accounts(swapAccounts())
+      .remainingAccounts([
         ...toRemainingAccounts(sellIx.keys),
+        ...toRemainingAccounts(buyIx.keys),
       ])
+      .view();
+
+    assert.isTrue(preview.isBuy);
+    assert.equal(preview.sum.toNumber(), 3);
+    assert.equal(preview.routeIndex, 1);
+    assert.equal(preview.selectedAccounts, buyIx.keys.length);
+    assert.isTrue(preview.destination.equals(userDestination));
+    assert.equal(preview.destinationBalance.toString(), destinationBefore.toString());
+
+    const destinationAfter = (await getAccount(
+      provider.connection,
+      userDestination
+    )).amount;
+    assert.equal(destinationAfter, destinationBefore, "Preview must not swap");
+  });
+
+  it("rejects routes whose lengths do not cover the accounts", async () => {
+    const buyIx = await buildMockSwapIx(
+      userSource,
+      userDestination,
+      BUY_AMOUNT
+    );
+
+    try {
+      await program.methods
+        .executeParitySwap([new BN(1)], { modulo: {} }, [toRoute(buyIx, 0n)])
+        .accounts(swapAccounts())
+        .remainingAccounts([
+          ...toRemainingAccounts(buyIx.keys),
+          ...toRemainingAccounts(buyIx.keys),
+        ])
+        .rpc();
+      assert.fail("Mismatched account split should fail");
+    } catch (e) {
+      assert.include(e.toString(), "InvalidAccountSplit");
+    }
+  });
+
+  it("fails when the chosen route delivers less than the minimum", async () => {
+    const buyIx = await buildMockSwapIx(
+      userSource,
+      userDestination,
+      BUY_AMOUNT
+    );
+    const sellIx = await buildMockSwapIx(
+      userDestination,
+      userSource,
+      BUY_AMOUNT
+    );
+
+    const expectedOut = await quote(BUY_AMOUNT, true);
+    const destinationBefore = (await getAccount(
+      provider.connection,
+      userDestination
+    )).amount;
+
+    try {
+      await program.methods
+        .executeParitySwap(
+          [new BN(3)], // odd -> buy
+          { parity: {} },
+          [toRoute(sellIx, 0n), toRoute(buyIx, expectedOut + 1n)]
+        )
+        .accounts(swapAccounts())
+        .remainingAccounts([
+          ...toRemainingAccounts(sellIx.keys),
+          ...toRemainingAccounts(buyIx.keys),
+        ])
+        .rpc();
+      assert.fail("Swap below min output should fail");
+    } catch (e) {
+      assert.include(e.toString(), "SlippageExceeded");
+    }
+
+    const destinationAfter = (await getAccount(
+      provider.connection,
+      userDestination
+    )).amount;
+    assert.equal(destinationAfter, destinationBefore, "Swap must be rolled back");
+  });
+
+  // Borsh encoding of the `routes` argument: u32 length, then per route
+  // `ix_data` (u32 length + bytes), `accounts_len`, `destination_index` and
+  // `min_amount_out` (u64).
+  const encodeRoutes = (routes: ReturnType<typeof toRoute>[]) => {
+    const parts = [Buffer.alloc(4)];
+    parts[0].writeUInt32LE(routes.length);
+    for (const route of routes) {
+      const len = Buffer.alloc(4);
+      len.writeUInt32LE(route.ixData.length);
+      const tail = Buffer.alloc(10);
+      tail.writeUInt8(route.accountsLen, 0);
+      tail.writeUInt8(route.destinationIndex, 1);
+      tail.writeBigUInt64LE(BigInt(route.minAmountOut.toString()), 2);
+      parts.push(len, route.ixData, tail);
+    }
+    return Buffer.concat(parts);
+  };
+
+  it("swaps along the route picked by revealed commit-reveal randomness", async () => {
+    // Both routes credit the same account with different amounts.
+    const amounts = [BUY_AMOUNT, 2n * BUY_AMOUNT];
+    const routeIxs = await Promise.all(
+      amounts.map((amount) =>
+        buildMockSwapIx(userSource, userDestination, amount)
+      )
+    );
+    const routes = routeIxs.map((ix) => toRoute(ix, 0n));
+    const remaining = routeIxs.flatMap((ix) => toRemainingAccounts(ix.keys));
+
+    const sha256 = (...parts: Buffer[]) =>
+      parts.reduce((h, part) => h.update(part), createHash("sha256")).digest();
+    const routesHash = sha256(encodeRoutes(routes));
+    const accountsHash = sha256(...remaining.map((a) => a.pubkey.toBuffer()));
+    const secret = Keypair.generate().publicKey.toBuffer();
+    const commitment = sha256(secret, routesHash, accountsHash);
+    const [commitmentPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("commitment"), authority.publicKey.toBuffer()],
+      program.programId
+    );
+
+    // The commitment holds the deposit until the reveal.
+    const deposit = 1_000_000;
+    await program.methods
+      .setCommitDeposit(new BN(deposit))
+      .accounts({ config: configPda, admin: authority.publicKey })
       .rpc();
+    await program.methods
+      .commitRandomness([...commitment])
+      .accounts({
+        authority: authority.publicKey,
+        config: configPda,
+        commitment: commitmentPda,
+      })
+      .rpc();
+    const { slot: commitSlot, deposit: recorded } =
+      await program.account.commitment.fetch(commitmentPda);
+    assert.equal(recorded.toNumber(), deposit);
+    const { lamports, data } = await provider.connection.getAccountInfo(
+      commitmentPda
+    );
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(
+      data.length
+    );
+    assert.equal(lamports, rent + deposit);
 
-    const userSourceAfter = (await getAccount(
+    try {
+      await program.methods
+        .cancelCommitment()
+        .accounts({
+          authority: authority.publicKey,
+          config: configPda,
+          admin: authority.publicKey,
+          commitment: commitmentPda,
+        })
+        .rpc();
+      assert.fail("A commitment that can still be revealed should not cancel");
+    } catch (e) {
+      assert.include(e.toString(), "CommitmentNotExpired");
+    }
+    while ((await provider.connection.getSlot()) <= commitSlot.toNumber()) {
+      await sleep(200);
+    }
+
+    const reveal = (revealed: Buffer, revealedRoutes = routes) =>
+      program.methods
+        .executeCommittedSwap([...revealed], revealedRoutes)
+        .accounts({
+          authority: authority.publicKey,
+          config: configPda,
+          commitment: commitmentPda,
+          slotHashes: anchor.web3.SYSVAR_SLOT_HASHES_PUBKEY,
+          dexProgram: MOCK_SWAP_PROGRAM_ID,
+        })
+        .remainingAccounts(remaining);
+
+    try {
+      await reveal(Buffer.alloc(32)).rpc();
+      assert.fail("Wrong secret should not reveal");
+    } catch (e) {
+      assert.include(e.toString(), "CommitmentMismatch");
+    }
+    try {
+      await reveal(secret, [routes[1], routes[0]]).rpc();
+      assert.fail("Routes other than the committed ones should not reveal");
+    } catch (e) {
+      assert.include(e.toString(), "CommitmentMismatch");
+    }
+
+    const expectedOuts = [
+      await quote(amounts[0], true),
+      await quote(amounts[1], true),
+    ];
+    const destinationBefore = (await getAccount(
       provider.connection,
-      userSource
+      userDestination
     )).amount;
-    const poolSourceAfter = (await getAccount(
+    const signature = await reveal(secret).rpc({ commitment: "confirmed" });
+    const destinationAfter = (await getAccount(
       provider.connection,
-      poolSource
+      userDestination
     )).amount;
 
-    assert.equal(
-      userSourceBefore - userSourceAfter,
-      BUY_AMOUNT,
-      "Sell branch should debit user source"
+    // Recompute the decision from the emitted inputs.
+    const tx = await provider.connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    const [event] = [...parser.parseLogs(tx.meta.logMessages)];
+    assert.equal(event.name, "committedSwapDecision");
+    const { data } = event;
+    assert.equal(data.commitSlot.toNumber(), commitSlot.toNumber());
+    assert.deepEqual(data.routesHash, [...routesHash]);
+    assert.deepEqual(data.accountsHash, [...accountsHash]);
+    const randomness = createHash("sha256")
+      .update(Buffer.from(data.secret))
+      .update(Buffer.from(data.slotHash))
+      .digest();
+    assert.deepEqual([...randomness], data.randomness);
+    const routeIndex = Number(randomness.readBigUInt64LE(0) % 2n);
+    assert.equal(data.routeIndex, routeIndex);
+    assert.equal(destinationAfter - destinationBefore, expectedOuts[routeIndex]);
+
+    assert.isNull(
+      await provider.connection.getAccountInfo(commitmentPda),
+      "Commitment should be closed after the reveal"
     );
-    assert.equal(
-      poolSourceAfter - poolSourceBefore,
-      BUY_AMOUNT,
-      "Sell branch should credit pool source"
+    await program.methods
+      .setCommitDeposit(new BN(0))
+      .accounts({ config: configPda, admin: authority.publicKey })
+      .rpc();
+  });
+
+  describe("vault mode", () => {
+    const vaultId = new BN(Date.now());
+    const operator = Keypair.generate();
+    const [vaultPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vault"),
+        authority.publicKey.toBuffer(),
+        vaultId.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    let vaultSource: PublicKey;
+    let vaultDestination: PublicKey;
+
+    // The router signs for the vault, so nothing in the transaction does.
+    const toVaultRemainingAccounts = (keys: anchor.web3.AccountMeta[]) =>
+      toRemainingAccounts(keys).map((k) => ({ ...k, isSigner: false }));
+
+    const vaultSwap = (
+      ix: anchor.web3.TransactionInstruction,
+      signer: Keypair = payer,
+      route = toRoute(ix, 1n),
+      keys = ix.keys
+    ) =>
+      program.methods
+        .executeVaultSwap([new BN(0)], { modulo: {} }, [route])
+        .accounts({
+          authority: signer.publicKey,
+          config: configPda,
+          vault: vaultPda,
+          dexProgram: MOCK_SWAP_PROGRAM_ID,
+        })
+        .remainingAccounts(toVaultRemainingAccounts(keys))
+        .signers(signer === payer ? [] : [signer]);
+
+    // Vault swaps of mintA for mintB need the owner's limit for that pair.
+    const PRICE_SCALE = 1_000_000_000n;
+    const setLimit = (maxAmountIn: bigint, minPrice = 0n, owner = payer) =>
+      program.methods
+        .setVaultLimit(
+          mintA,
+          mintB,
+          new BN(maxAmountIn.toString()),
+          new BN(minPrice.toString())
+        )
+        .accounts({ owner: owner.publicKey, vault: vaultPda })
+        .signers(owner === payer ? [] : [owner])
+        .rpc();
+
+    before(async () => {
+      await program.methods
+        .createVault(vaultId, operator.publicKey)
+        .accounts({ owner: authority.publicKey, vault: vaultPda })
+        .rpc();
+      await setLimit(5n * BUY_AMOUNT);
+
+      vaultSource = await createAccount(
+        provider.connection,
+        payer,
+        mintA,
+        vaultPda,
+        Keypair.generate()
+      );
+      vaultDestination = await createAccount(
+        provider.connection,
+        payer,
+        mintB,
+        vaultPda,
+        Keypair.generate()
+      );
+      await mintTo(
+        provider.connection,
+        payer,
+        mintA,
+        vaultSource,
+        authority.publicKey,
+        5n * BUY_AMOUNT
+      );
+    });
+
+    it("operator swaps vault funds with the router signing for the vault", async () => {
+      const ix = await buildMockSwapIx(
+        vaultSource,
+        vaultDestination,
+        BUY_AMOUNT,
+        vaultPda
+      );
+      const expectedOut = await quote(BUY_AMOUNT, true);
+      await vaultSwap(ix, operator).rpc();
+
+      assert.equal(await balance(vaultDestination), expectedOut);
+    });
+
+    it("keeps vault swaps within the owner's limits", async () => {
+      const ix = await buildMockSwapIx(
+        vaultSource,
+        vaultDestination,
+        BUY_AMOUNT,
+        vaultPda
+      );
+      const expectedOut = await quote(BUY_AMOUNT, true);
+      const before = await balance(vaultDestination);
+      const expectFailure = async (error: string) => {
+        try {
+          await vaultSwap(ix, operator).rpc();
+          assert.fail(`Vault swap should fail with ${error}`);
+        } catch (e) {
+          assert.include(e.toString(), error);
+        }
+      };
+
+      try {
+        try {
+          await setLimit(BUY_AMOUNT, 0n, operator);
+          assert.fail("Operator should not set limits");
+        } catch (e) {
+          assert.include(e.toString(), "ConstraintSeeds");
+        }
+
+        await setLimit(BUY_AMOUNT - 1n);
+        await expectFailure("VaultAmountInExceeded");
+        // One unit above the price the pool gives.
+        await setLimit(BUY_AMOUNT, (expectedOut * PRICE_SCALE) / BUY_AMOUNT + 1n);
+        await expectFailure("VaultPriceTooLow");
+        await setLimit(0n);
+        await expectFailure("VaultPairNotAllowed");
+        assert.equal(await balance(vaultDestination), before);
+
+        await setLimit(BUY_AMOUNT, (expectedOut * PRICE_SCALE) / BUY_AMOUNT);
+        await vaultSwap(ix, operator).rpc();
+        assert.equal((await balance(vaultDestination)) - before, expectedOut);
+      } finally {
+        await setLimit(5n * BUY_AMOUNT);
+      }
+    });
+
+    it("rejects vault swaps paying out of the vault", async () => {
+      const ix = await buildMockSwapIx(
+        vaultSource,
+        userDestination,
+        BUY_AMOUNT,
+        vaultPda
+      );
+      try {
+        await vaultSwap(ix).rpc();
+        assert.fail("Vault swap to a foreign account should fail");
+      } catch (e) {
+        assert.include(e.toString(), "DestinationNotVault");
+      }
+    });
+
+    it("rejects vault swaps without a minimum output", async () => {
+      const ix = await buildMockSwapIx(
+        vaultSource,
+        vaultDestination,
+        BUY_AMOUNT,
+        vaultPda
+      );
+      try {
+        await vaultSwap(ix, operator, toRoute(ix, 0n)).rpc();
+        assert.fail("Vault swap without a minimum output should fail");
+      } catch (e) {
+        assert.include(e.toString(), "InvalidAmount");
+      }
+    });
+
+    it("rejects vault swaps naming an idle vault account while paying elsewhere", async () => {
+      const idle = await createAccount(
+        provider.connection,
+        payer,
+        mintB,
+        vaultPda,
+        Keypair.generate()
+      );
+      // The DEX pays `userDestination`; the route claims the appended idle
+      // vault account is its destination.
+      const ix = await buildMockSwapIx(
+        vaultSource,
+        userDestination,
+        BUY_AMOUNT,
+        vaultPda
+      );
+      const keys = [
+        ...ix.keys,
+        { pubkey: idle, isWritable: true, isSigner: false },
+      ];
+      const route = {
+        ...toRoute(ix, 1n),
+        accountsLen: keys.length,
+        destinationIndex: keys.length - 1,
+      };
+      const before = await balance(userDestination);
+      try {
+        await vaultSwap(ix, operator, route, keys).rpc();
+        assert.fail("Vault output paid to a foreign account should fail");
+      } catch (e) {
+        assert.include(e.toString(), "SlippageExceeded");
+      }
+      assert.equal(await balance(userDestination), before);
+    });
+
+    it("rejects callers that are neither owner nor operator", async () => {
+      const ix = await buildMockSwapIx(
+        vaultSource,
+        vaultDestination,
+        BUY_AMOUNT,
+        vaultPda
+      );
+      try {
+        await vaultSwap(ix, Keypair.generate()).rpc();
+        assert.fail("Outsider should not trade the vault");
+      } catch (e) {
+        assert.include(e.toString(), "Unauthorized");
+      }
+    });
+
+    it("only the owner withdraws", async () => {
+      const amount = new BN((await balance(vaultDestination)).toString());
+      const withdrawAccounts = (owner: PublicKey) => ({
+        owner,
+        vault: vaultPda,
+        vaultTokenAccount: vaultDestination,
+        destination: userDestination,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      });
+
+      try {
+        await program.methods
+          .withdrawFromVault(amount)
+          .accounts(withdrawAccounts(operator.publicKey))
+          .signers([operator])
+          .rpc();
+        assert.fail("Operator should not withdraw");
+      } catch (e) {
+        // The vault seeds are derived from the signing owner.
+        assert.include(e.toString(), "ConstraintSeeds");
+      }
+
+      const before = await balance(userDestination);
+      await program.methods
+        .withdrawFromVault(amount)
+        .accounts(withdrawAccounts(authority.publicKey))
+        .rpc();
+      assert.equal(
+        (await balance(userDestination)) - before,
+        BigInt(amount.toString())
+      );
+      assert.equal(await balance(vaultDestination), 0n);
+    });
+  });
+
+  it("rejects DEX programs that are not on the allowlist", async () => {
+    const buyIx = await buildMockSwapIx(
+      userSource,
+      userDestination,
+      BUY_AMOUNT
     );
+
+    try {
+      await program.methods
+        .executeParitySwap([new BN(1)], { modulo: {} }, [toRoute(buyIx, 0n)])
+        .accounts(swapAccounts(TOKEN_PROGRAM_ID))
+        .remainingAccounts(toRemainingAccounts(buyIx.keys))
+        .rpc();
+      assert.fail("Swap to a non-allowlisted program should fail");
+    } catch (e) {
+      assert.include(e.toString(), "DexProgramNotAllowed");
+    }
   });
-});
 
+  it("only the admin can change the allowlist", async () => {
+    const outsider = Keypair.generate();
+    try {
+      await program.methods
+        .removeAllowedProgram(MOCK_SWAP_PROGRAM_ID)
+        .accounts({ config: configPda, admin: outsider.publicKey })
+        .signers([outsider])
+        .rpc();
+      assert.fail("Non-admin should not edit the allowlist");
+    } catch (e) {
+      assert.include(e.toString(), "Unauthorized");
+    }
+  });
+
+  it("throttles repeated swaps of one authority", async () => {
+    // Swaps here spend mintA, so that is the capped mint.
+    const setLimits = (cooldown: number, window: number, cap: bigint) =>
+      program.methods
+        .setRateLimits(
+          new BN(cooldown),
+          new BN(window),
+          new BN(cap.toString()),
+          mintA
+        )
+        .accounts({ config: configPda, admin: authority.publicKey })
+        .rpc();
+    const swap = async (amount: bigint) => {
+      const ix = await buildMockSwapIx(userSource, userDestination, amount);
+      return program.methods
+        .executeParitySwap([new BN(1)], { modulo: {} }, [toRoute(ix, 0n)])
+        .accounts(swapAccounts())
+        .remainingAccounts(toRemainingAccounts(ix.keys))
+        .rpc();
+    };
+
+    try {
+      // Record a swap, then make the next one fall inside the cooldown.
+      await swap(BUY_AMOUNT);
+      await setLimits(1_000, 0, 0n);
+      try {
+        await swap(BUY_AMOUNT);
+        assert.fail("Second swap inside the cooldown should fail");
+      } catch (e) {
+        assert.include(e.toString(), "CooldownActive");
+      }
+
+      await setLimits(0, 1_000, BUY_AMOUNT);
+      try {
+        await swap(2n * BUY_AMOUNT);
+        assert.fail("Swap above the window cap should fail");
+      } catch (e) {
+        assert.include(e.toString(), "VolumeCapExceeded");
+      }
+
+      try {
+        await setLimits(0, 0, BUY_AMOUNT);
+        assert.fail("A volume cap without a window should be rejected");
+      } catch (e) {
+        assert.include(e.toString(), "InvalidRateWindow");
+      }
+    } finally {
+      await setLimits(0, 0, 0n);
+    }
+  });
+
+  describe("typed Raydium swaps against mock_swap", () => {
+    // mock_swap answers Raydium AMM v4 and CLMM swaps: the pool account is the
+    // AMM/pool state, vault A (mintA) the coin/base side, vault B the PC/quote
+    // side. Pool accounts it does not read get throwaway keys.
+    const unused = () => Keypair.generate().publicKey;
+    const amounts = (amountIn: bigint, minOut: bigint) => ({
+      amountIn: new BN(amountIn.toString()),
+      minAmountOut: new BN(minOut.toString()),
+    });
+
+    const ammSwap = (numbers: number[], buyMinOut: bigint) =>
+      program.methods
+        .paritySwapRaydiumAmm(
+          numbers.map((n) => new BN(n)),
+          { parity: {} },
+          amounts(BUY_AMOUNT, buyMinOut),
+          amounts(BUY_AMOUNT, 0n)
+        )
+        .accounts({
+          authority: authority.publicKey,
+          config: configPda,
+          ammProgram: MOCK_SWAP_PROGRAM_ID,
+          amm: ammPool,
+          ammAuthority: unused(),
+          ammOpenOrders: unused(),
+          ammTargetOrders: unused(),
+          poolCoinVault: ammVaultA,
+          poolPcVault: ammVaultB,
+          marketProgram: unused(),
+          market: unused(),
+          marketBids: unused(),
+          marketAsks: unused(),
+          marketEventQueue: unused(),
+          marketCoinVault: unused(),
+          marketPcVault: unused(),
+          marketVaultSigner: unused(),
+          userCoinAccount: userSource,
+          userPcAccount: userDestination,
+        });
+
+    const clmmSwap = (numbers: number[], buyMinOut: bigint) =>
+      program.methods
+        .paritySwapRaydiumClmm(
+          numbers.map((n) => new BN(n)),
+          { parity: {} },
+          { ...amounts(BUY_AMOUNT, buyMinOut), sqrtPriceLimitX64: new BN(0) },
+          { ...amounts(BUY_AMOUNT, 0n), sqrtPriceLimitX64: new BN(0) },
+          1
+        )
+        .accounts({
+          authority: authority.publicKey,
+          config: configPda,
+          clmmProgram: MOCK_SWAP_PROGRAM_ID,
+          ammConfig: unused(),
+          poolState: ammPool,
+          observationState: unused(),
+          baseVault: ammVaultA,
+          quoteVault: ammVaultB,
+          userBaseAccount: userSource,
+          userQuoteAccount: userDestination,
+        })
+        // One tick array per direction, buy first.
+        .remainingAccounts(
+          [unused(), unused()].map((pubkey) => ({
+            pubkey,
+            isWritable: true,
+            isSigner: false,
+          }))
+        );
+
+    for (const [name, swap] of [
+      ["AMM v4", ammSwap],
+      ["CLMM", clmmSwap],
+    ] as const) {
+      it(`${name}: buys on an odd sum, sells on an even one`, async () => {
+        // Buy spends B (quote) for A (base).
+        const buyOut = await quote(BUY_AMOUNT, false);
+        const sourceBefore = await balance(userSource);
+        await swap([1], buyOut).rpc();
+        assert.equal((await balance(userSource)) - sourceBefore, buyOut);
+
+        const sellOut = await quote(BUY_AMOUNT, true);
+        const destinationBefore = await balance(userDestination);
+        await swap([2], 0n).rpc();
+        assert.equal(
+          (await balance(userDestination)) - destinationBefore,
+          sellOut
+        );
+      });
+
+      it(`${name}: enforces the minimum output`, async () => {
+        const buyOut = await quote(BUY_AMOUNT, false);
+        const sourceBefore = await balance(userSource);
+        try {
+          await swap([1], buyOut + 1n).rpc();
+          assert.fail("Swap below its minimum output should fail");
+        } catch (e) {
+          assert.include(e.toString(), "SlippageExceeded");
+        }
+        assert.equal(await balance(userSource), sourceBefore);
+      });
+    }
+
+    it("forwards both the 18- and 17-account AMM v4 layouts", async () => {
+      // Raydium's swap_base_in with and without `amm_target_orders`.
+      for (const withTargetOrders of [true, false]) {
+        const data = Buffer.alloc(17);
+        data.writeUInt8(9, 0);
+        data.writeBigUInt64LE(BUY_AMOUNT, 1);
+        data.writeBigUInt64LE(0n, 9);
+        const meta = (pubkey: PublicKey, isWritable = false) => ({
+          pubkey,
+          isWritable,
+          isSigner: false,
+        });
+        const keys = [
+          meta(TOKEN_PROGRAM_ID),
+          meta(ammPool, true),
+          meta(unused()), // amm_authority
+          meta(unused()), // amm_open_orders
+          ...(withTargetOrders ? [meta(unused())] : []),
+          meta(ammVaultA, true),
+          meta(ammVaultB, true),
+          ...Array.from({ length: 8 }, () => meta(unused())), // market accounts
+          meta(userSource, true),
+          meta(userDestination, true),
+          { pubkey: authority.publicKey, isWritable: false, isSigner: true },
+        ];
+        assert.equal(keys.length, withTargetOrders ? 18 : 17);
+
+        const expectedOut = await quote(BUY_AMOUNT, true);
+        const destinationBefore = await balance(userDestination);
+        await program.methods
+          .executeParitySwap([new BN(0)], { modulo: {} }, [
+            {
+              ixData: data,
+              accountsLen: keys.length,
+              destinationIndex: keys.length - 2,
+              minAmountOut: new BN(expectedOut.toString()),
+            },
+          ])
+          .accounts(swapAccounts())
+          .remainingAccounts(keys)
+          .rpc();
+        assert.equal(
+          (await balance(userDestination)) - destinationBefore,
+          expectedOut
+        );
+      }
+    });
+  });
+
+  describe("misbehaving DEX", () => {
+    let failureConfig: PublicKey;
+
+    const setMode = (mode: object) =>
+      mockProgram.methods
+        .setFailureMode(mode)
+        .accounts({ authority: authority.publicKey, failureConfig })
+        .rpc();
+
+    // Buy through mock_swap with its failure config (and whatever the mode
+    // needs) appended to the swap accounts; only the tx outcome matters.
+    const expectRejected = async (
+      minAmountOut: bigint,
+      pattern: RegExp,
+      extra: PublicKey[] = []
+    ) => {
+      const ix = await buildMockSwapIx(userSource, userDestination, BUY_AMOUNT);
+      ix.keys.push(
+        ...[failureConfig, ...extra].map((pubkey) => ({
+          pubkey,
+          isSigner: false,
+          isWritable: false,
+        }))
+      );
+      const destinationBefore = await balance(userDestination);
+      try {
+        await program.methods
+          .executeParitySwap([new BN(1)], { modulo: {} }, [
+            toRoute(ix, minAmountOut),
+          ])
+          .accounts(swapAccounts())
+          .remainingAccounts(toRemainingAccounts(ix.keys))
+          .rpc();
+        assert.fail("Swap through a misbehaving DEX should fail");
+      } catch (e) {
+        assert.match([e.toString(), ...(e.logs ?? [])].join("\n"), pattern);
+      }
+      assert.equal(
+        await balance(userDestination),
+        destinationBefore,
+        "Swap must be rolled back"
+      );
+    };
+
+    before(async () => {
+      [failureConfig] = PublicKey.findProgramAddressSync(
+        [Buffer.from("failure"), ammPool.toBuffer()],
+        MOCK_SWAP_PROGRAM_ID
+      );
+      await mockProgram.methods
+        .initializeFailureConfig()
+        .accounts({
+          authority: authority.publicKey,
+          pool: ammPool,
+          failureConfig,
+        })
+        .rpc();
+    });
+
+    after(async () => {
+      await setMode({ disabled: {} });
+    });
+
+    it("propagates the DEX error", async () => {
+      await setMode({ error: { code: 42 } });
+      await expectRejected(0n, /custom program error: 0x2a/);
+    });
+
+    it("enforces the route minimum when the DEX pays short", async () => {
+      await setMode({ shortOutput: { outputBps: 5_000 } });
+      await expectRejected(await quote(BUY_AMOUNT, true), /SlippageExceeded/);
+    });
+
+    it("fails when the DEX exhausts the compute budget", async () => {
+      await setMode({ burnCompute: { iterations: 0xffffffff } });
+      await expectRejected(0n, /exceeded CUs meter|ComputationalBudgetExceeded/);
+    });
+
+    it("cannot be re-entered from the DEX", async () => {
+      await setMode({ reenter: { program: program.programId } });
+      await expectRejected(0n, /reentrancy/i, [program.programId]);
+    });
+  });
+});
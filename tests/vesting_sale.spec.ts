@@ -1,14 +1,21 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { PublicKey, Keypair, SystemProgram } from "@solana/web3.js";
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
+} from "@solana/web3.js";
 import BN from "bn.js";
 import {
   TOKEN_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   createMint,
   createAccount,
+  createAssociatedTokenAccount,
   mintTo,
   getAccount,
+  getMint,
 } from "@solana/spl-token";
 import { assert } from "chai";
 
@@ -33,6 +40,10 @@ describe("vesting_sale", () => {
   let buyer2: Keypair;
   let buyer2UsdcAccount: PublicKey;
   let vesting2Account: PublicKey;
+  let positionMint: Keypair;
+  let positionVesting: PublicKey;
+  let teamMember: Keypair;
+  let grantAccount: PublicKey;
 
   // Constants
   const CLIFF_DURATION = 90 * 24 * 60 * 60; // 3 months
@@ -353,6 +364,133 @@ describe("vesting_sale", () => {
     );
   });
 
+  it("Buyer purchases a vesting position NFT", async () => {
+    const usdcAmount = 20 * 10 ** USDC_DECIMALS; // 20 USDC
+    const expectedVectAmount = 400_000_000; // 400 VECT
+
+    positionMint = Keypair.generate();
+    [positionVesting] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("position"),
+        saleState.toBuffer(),
+        positionMint.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const buyerPositionAccount = await anchor.utils.token.associatedAddress({
+      mint: positionMint.publicKey,
+      owner: buyer.publicKey,
+    });
+
+    const tx = await program.methods
+      .buyPositionWithUsdc(new BN(usdcAmount))
+      .accounts({
+        saleState,
+        positionMint: positionMint.publicKey,
+        positionVesting,
+        buyer: buyer.publicKey,
+        buyerUsdcAccount,
+        buyerPositionAccount,
+        usdcTreasury,
+        vectVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer, positionMint])
+      .rpc();
+
+    console.log("Buy position tx:", tx);
+
+    const vestingData = await program.account.vesting.fetch(positionVesting);
+    assert.equal(
+      vestingData.positionMint.toString(),
+      positionMint.publicKey.toString()
+    );
+    assert.equal(vestingData.totalVectAmount.toNumber(), expectedVectAmount);
+
+    const nft = await getAccount(provider.connection, buyerPositionAccount);
+    assert.equal(nft.amount.toString(), "1");
+    const mintInfo = await getMint(provider.connection, positionMint.publicKey);
+    assert.isNull(mintInfo.mintAuthority, "Position supply must be fixed");
+    console.log("✅ Position NFT minted for", expectedVectAmount, "VECT");
+  });
+
+  it("Only the position holder can claim", async () => {
+    // buyer2 holds no position token: create an empty account for them
+    const buyer2PositionAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      payer,
+      positionMint.publicKey,
+      buyer2.publicKey
+    );
+    const buyer2VectAccount = await anchor.utils.token.associatedAddress({
+      mint: vectMint,
+      owner: buyer2.publicKey,
+    });
+
+    try {
+      await program.methods
+        .claimPosition()
+        .accounts({
+          saleState,
+          positionVesting,
+          positionMint: positionMint.publicKey,
+          holder: buyer2.publicKey,
+          holderPositionAccount: buyer2PositionAccount,
+          vectMint,
+          holderVectAccount: buyer2VectAccount,
+          vectVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer2])
+        .rpc();
+
+      assert.fail("Should have failed - signer holds no position");
+    } catch (error) {
+      assert.include(error.toString(), "NotPositionHolder");
+      console.log("✅ Correctly rejected claim from non-holder");
+    }
+  });
+
+  it("Cannot claim a position before cliff", async () => {
+    const buyerPositionAccount = await anchor.utils.token.associatedAddress({
+      mint: positionMint.publicKey,
+      owner: buyer.publicKey,
+    });
+    const buyerVectAccount = await anchor.utils.token.associatedAddress({
+      mint: vectMint,
+      owner: buyer.publicKey,
+    });
+
+    try {
+      await program.methods
+        .claimPosition()
+        .accounts({
+          saleState,
+          positionVesting,
+          positionMint: positionMint.publicKey,
+          holder: buyer.publicKey,
+          holderPositionAccount: buyerPositionAccount,
+          vectMint,
+          holderVectAccount: buyerVectAccount,
+          vectVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer])
+        .rpc();
+
+      assert.fail("Should have failed - cliff not reached");
+    } catch (error) {
+      assert.include(error.toString(), "CliffNotReached");
+      console.log("✅ Correctly prevented position claim before cliff");
+    }
+  });
+
   it("Admin can pause the sale", async () => {
     const tx = await program.methods
       .pauseSale()
@@ -468,6 +606,73 @@ describe("vesting_sale", () => {
     }
   });
 
+  it("Batch claim skips vestings with nothing vested", async () => {
+    const buyerVectAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      payer,
+      vectMint,
+      buyer.publicKey
+    );
+    const buyer2VectAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      payer,
+      vectMint,
+      buyer2.publicKey
+    );
+    const vaultBefore = await getAccount(provider.connection, vectVault);
+
+    const tx = await program.methods
+      .batchClaim()
+      .accounts({
+        saleState,
+        vectVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        { pubkey: vestingAccount, isWritable: true, isSigner: false },
+        { pubkey: buyerVectAccount, isWritable: true, isSigner: false },
+        { pubkey: vesting2Account, isWritable: true, isSigner: false },
+        { pubkey: buyer2VectAccount, isWritable: true, isSigner: false },
+      ])
+      .rpc();
+
+    console.log("Batch claim tx:", tx);
+
+    // Both vestings are still before the cliff, so nothing moves
+    const vaultAfter = await getAccount(provider.connection, vectVault);
+    assert.equal(vaultAfter.amount.toString(), vaultBefore.amount.toString());
+    const vestingData = await program.account.vesting.fetch(vestingAccount);
+    assert.equal(vestingData.claimedAmount.toNumber(), 0);
+    console.log("✅ Batch claim skipped unvested accounts");
+  });
+
+  it("Batch claim rejects a token account not owned by the beneficiary", async () => {
+    const buyer2VectAccount = await anchor.utils.token.associatedAddress({
+      mint: vectMint,
+      owner: buyer2.publicKey,
+    });
+
+    try {
+      await program.methods
+        .batchClaim()
+        .accounts({
+          saleState,
+          vectVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: vestingAccount, isWritable: true, isSigner: false },
+          { pubkey: buyer2VectAccount, isWritable: true, isSigner: false },
+        ])
+        .rpc();
+
+      assert.fail("Should have failed - destination not owned by beneficiary");
+    } catch (error) {
+      assert.include(error.toString(), "InvalidBeneficiaryAccount");
+      console.log("✅ Correctly rejected mismatched beneficiary account");
+    }
+  });
+
   it("Admin withdraws USDC", async () => {
     // Create authority's USDC account if needed
     const authorityUsdcAccount = await createAccount(
@@ -508,6 +713,132 @@ describe("vesting_sale", () => {
     console.log("✅ Withdrew", withdrawAmount.toString(), "USDC");
   });
 
+  it("Admin cannot grant VECT already owed to buyers", async () => {
+    const outsider = Keypair.generate();
+    const [outsiderGrant] = PublicKey.findProgramAddressSync(
+      [Buffer.from("grant"), saleState.toBuffer(), outsider.publicKey.toBuffer()],
+      program.programId
+    );
+    const sale = await program.account.saleState.fetch(saleState);
+    const outstanding = sale.totalVectSold
+      .add(sale.totalVectGranted)
+      .sub(sale.totalVectClaimed);
+    const vault = await getAccount(provider.connection, vectVault);
+    // One more than the vault holds beyond what is already owed.
+    const amount = new BN(vault.amount.toString()).sub(outstanding).addn(1);
+
+    try {
+      await program.methods
+        .createGrant(amount, new BN(0), new BN(365 * 24 * 60 * 60))
+        .accounts({
+          saleState,
+          grant: outsiderGrant,
+          beneficiary: outsider.publicKey,
+          authority: authority.publicKey,
+          vectVault,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Should have failed - vault tokens are owed to buyers");
+    } catch (error) {
+      assert.include(error.toString(), "InsufficientVaultBalance");
+      console.log("✅ Grant larger than the unreserved vault balance rejected");
+    }
+  });
+
+  it("Admin creates a team grant with its own schedule", async () => {
+    teamMember = Keypair.generate();
+    [grantAccount] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("grant"),
+        saleState.toBuffer(),
+        teamMember.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const grantAmount = 5_000 * 10 ** VECT_DECIMALS; // 5k VECT
+    const grantCliff = 180 * 24 * 60 * 60; // 6 months
+    const grantVesting = 730 * 24 * 60 * 60; // 2 years
+
+    const tx = await program.methods
+      .createGrant(new BN(grantAmount), new BN(grantCliff), new BN(grantVesting))
+      .accounts({
+        saleState,
+        grant: grantAccount,
+        beneficiary: teamMember.publicKey,
+        authority: authority.publicKey,
+        vectVault,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    console.log("Create grant tx:", tx);
+
+    const grant = await program.account.grant.fetch(grantAccount);
+    assert.equal(grant.beneficiary.toString(), teamMember.publicKey.toString());
+    assert.equal(grant.totalVectAmount.toNumber(), grantAmount);
+    assert.equal(grant.cliffDuration.toNumber(), grantCliff);
+    assert.equal(grant.vestingDuration.toNumber(), grantVesting);
+    assert.equal(grant.isRevoked, false);
+
+    const saleStateAccount = await program.account.saleState.fetch(saleState);
+    assert.equal(saleStateAccount.totalVectGranted.toNumber(), grantAmount);
+    console.log("✅ Granted", grantAmount, "VECT");
+  });
+
+  it("Admin revokes the grant and unvested tokens return to the vault", async () => {
+    const tx = await program.methods
+      .revokeGrant()
+      .accounts({
+        saleState,
+        grant: grantAccount,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    console.log("Revoke grant tx:", tx);
+
+    // Still before the grant cliff, so nothing had vested
+    const grant = await program.account.grant.fetch(grantAccount);
+    assert.equal(grant.isRevoked, true);
+    assert.equal(grant.totalVectAmount.toNumber(), 0);
+
+    const saleStateAccount = await program.account.saleState.fetch(saleState);
+    assert.equal(saleStateAccount.totalVectGranted.toNumber(), 0);
+
+    try {
+      await program.methods
+        .revokeGrant()
+        .accounts({
+          saleState,
+          grant: grantAccount,
+          authority: authority.publicKey,
+        })
+        .rpc();
+
+      assert.fail("Should have failed - grant already revoked");
+    } catch (error) {
+      assert.include(error.toString(), "GrantAlreadyRevoked");
+      console.log("✅ Grant revoked once, second revoke rejected");
+    }
+  });
+
+  it("Grantee closes the revoked grant once nothing is left to claim", async () => {
+    const tx = await program.methods
+      .closeGrant()
+      .accounts({
+        grant: grantAccount,
+        beneficiary: teamMember.publicKey,
+      })
+      .signers([teamMember])
+      .rpc();
+
+    console.log("Close grant tx:", tx);
+
+    assert.isNull(await program.account.grant.fetchNullable(grantAccount));
+    console.log("✅ Revoked grant closed");
+  });
+
   it("Admin can end the sale", async () => {
     const tx = await program.methods
       .endSale()
@@ -551,6 +882,139 @@ describe("vesting_sale", () => {
     }
   });
 
+  it("Cannot transfer vesting while transfers are disabled", async () => {
+    const newOwner = Keypair.generate();
+    const [newVesting] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vesting"),
+        saleState.toBuffer(),
+        newOwner.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .transferVesting()
+        .accounts({
+          saleState,
+          vesting: vesting2Account,
+          newVesting,
+          beneficiary: buyer2.publicKey,
+          newBeneficiary: newOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([buyer2])
+        .rpc();
+
+      assert.fail("Should have failed - transfers disabled");
+    } catch (error) {
+      assert.include(error.toString(), "VestingTransfersDisabled");
+      console.log("✅ Correctly prevented transfer while disabled");
+    }
+  });
+
+  it("Beneficiary transfers vesting to a new wallet", async () => {
+    await program.methods
+      .setVestingTransfers(true)
+      .accounts({
+        saleState,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const newOwner = Keypair.generate();
+    const [newVesting] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("vesting"),
+        saleState.toBuffer(),
+        newOwner.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const before = await program.account.vesting.fetch(vesting2Account);
+
+    const tx = await program.methods
+      .transferVesting()
+      .accounts({
+        saleState,
+        vesting: vesting2Account,
+        newVesting,
+        beneficiary: buyer2.publicKey,
+        newBeneficiary: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer2])
+      .rpc();
+
+    console.log("Transfer vesting tx:", tx);
+
+    const after = await program.account.vesting.fetch(newVesting);
+    assert.equal(after.beneficiary.toString(), newOwner.publicKey.toString());
+    assert.equal(
+      after.totalVectAmount.toString(),
+      before.totalVectAmount.toString()
+    );
+    assert.equal(
+      after.claimedAmount.toString(),
+      before.claimedAmount.toString()
+    );
+    assert.equal(after.startTime.toString(), before.startTime.toString());
+
+    const oldAccount = await provider.connection.getAccountInfo(
+      vesting2Account
+    );
+    assert.isNull(oldAccount, "Old vesting should be closed");
+    console.log("✅ Vesting moved to", newOwner.publicKey.toString());
+  });
+
+  it("Cannot close a vesting with unclaimed tokens", async () => {
+    try {
+      await program.methods
+        .closeVesting()
+        .accounts({
+          vesting: vestingAccount,
+          beneficiary: buyer.publicKey,
+        })
+        .signers([buyer])
+        .rpc();
+
+      assert.fail("Should have failed - vesting not fully claimed");
+    } catch (error) {
+      assert.include(error.toString(), "VestingNotFullyClaimed");
+      console.log("✅ Correctly kept an unclaimed vesting open");
+    }
+  });
+
+  it("Cannot close the sale before the treasury is withdrawn", async () => {
+    const authorityVectAccount = await createAccount(
+      provider.connection,
+      payer,
+      vectMint,
+      authority.publicKey,
+      Keypair.generate()
+    );
+
+    try {
+      await program.methods
+        .closeSale()
+        .accounts({
+          saleState,
+          authority: authority.publicKey,
+          authorityVectAccount,
+          vectVault,
+          usdcTreasury,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      assert.fail("Should have failed - treasury still holds USDC");
+    } catch (error) {
+      assert.include(error.toString(), "TreasuryNotEmpty");
+      console.log("✅ Correctly kept an unsettled sale open");
+    }
+  });
+
   it("Fetch and display sale statistics", async () => {
     const saleStateAccount = await program.account.saleState.fetch(saleState);
 
@@ -586,3 +1050,408 @@ describe("vesting_sale", () => {
     console.log("   then call claim() periodically to receive vested tokens.");
   });
 });
+
+describe("vesting_sale with a short schedule", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.VestingSale as Program;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+
+  // A second sale, keyed by its own authority, whose tokens vest within
+  // seconds so claims and closes can run against the local validator
+  const CLIFF_DURATION = 1;
+  const VESTING_DURATION = 2;
+  const USDC_PRICE_PER_VECT = 50_000; // 0.05 USDC
+  const PURCHASE_USDC = 10_000_000; // 10 USDC
+  const PURCHASE_VECT = 200_000_000; // 200 VECT
+  const GRANT_VECT = 1_000_000_000; // 1k VECT
+  const FUND_VECT = 10_000_000_000; // 10k VECT
+
+  const saleAuthority = Keypair.generate();
+  const buyer = Keypair.generate();
+  const teamMember = Keypair.generate();
+  let vectMint: PublicKey;
+  let usdcMint: PublicKey;
+  let saleState: PublicKey;
+  let vectVault: PublicKey;
+  let usdcTreasury: PublicKey;
+  let vesting: PublicKey;
+  let grant: PublicKey;
+  let buyerUsdcAccount: PublicKey;
+  let buyerVectAccount: PublicKey;
+  let authorityVectAccount: PublicKey;
+
+  const pda = (...seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+  // The validator clock follows wall time, so poll it rather than sleep blindly
+  const waitForClock = async (unixTimestamp: number) => {
+    for (;;) {
+      const clock = await provider.connection.getAccountInfo(SYSVAR_CLOCK_PUBKEY);
+      if (Number(clock.data.readBigInt64LE(32)) >= unixTimestamp) return;
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  };
+
+  const vectBalance = async (account: PublicKey) =>
+    (await getAccount(provider.connection, account)).amount.toString();
+
+  const buy = () =>
+    program.methods
+      .buyWithUsdc(new BN(PURCHASE_USDC))
+      .accounts({
+        saleState,
+        vesting,
+        buyer: buyer.publicKey,
+        buyerUsdcAccount,
+        usdcTreasury,
+        vectVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer])
+      .rpc();
+
+  const claim = () =>
+    program.methods
+      .claim()
+      .accounts({
+        saleState,
+        vesting,
+        beneficiary: buyer.publicKey,
+        vectMint,
+        beneficiaryVectAccount: buyerVectAccount,
+        vectVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer])
+      .rpc();
+
+  before(async () => {
+    for (const wallet of [saleAuthority, buyer, teamMember]) {
+      const signature = await provider.connection.requestAirdrop(
+        wallet.publicKey,
+        10 * anchor.web3.LAMPORTS_PER_SOL
+      );
+      const latestBlockhash = await provider.connection.getLatestBlockhash();
+      await provider.connection.confirmTransaction({
+        signature,
+        ...latestBlockhash,
+      });
+    }
+
+    vectMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+    usdcMint = await createMint(provider.connection, payer, payer.publicKey, null, 6);
+
+    buyerUsdcAccount = await createAccount(
+      provider.connection,
+      payer,
+      usdcMint,
+      buyer.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      payer,
+      usdcMint,
+      buyerUsdcAccount,
+      payer,
+      100 * PURCHASE_USDC
+    );
+    buyerVectAccount = await anchor.utils.token.associatedAddress({
+      mint: vectMint,
+      owner: buyer.publicKey,
+    });
+    authorityVectAccount = await createAccount(
+      provider.connection,
+      payer,
+      vectMint,
+      saleAuthority.publicKey
+    );
+    await mintTo(
+      provider.connection,
+      payer,
+      vectMint,
+      authorityVectAccount,
+      payer,
+      FUND_VECT
+    );
+
+    saleState = pda(Buffer.from("sale"), saleAuthority.publicKey.toBuffer());
+    vectVault = pda(Buffer.from("vect_vault"), saleState.toBuffer());
+    usdcTreasury = pda(Buffer.from("usdc_treasury"), saleState.toBuffer());
+    vesting = pda(
+      Buffer.from("vesting"),
+      saleState.toBuffer(),
+      buyer.publicKey.toBuffer()
+    );
+    grant = pda(
+      Buffer.from("grant"),
+      saleState.toBuffer(),
+      teamMember.publicKey.toBuffer()
+    );
+
+    await program.methods
+      .initializeSale(
+        new BN(CLIFF_DURATION),
+        new BN(VESTING_DURATION),
+        new BN(USDC_PRICE_PER_VECT)
+      )
+      .accounts({
+        saleState,
+        authority: saleAuthority.publicKey,
+        vectMint,
+        usdcMint,
+        vectVault,
+        usdcTreasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([saleAuthority])
+      .rpc();
+
+    await program.methods
+      .adminFundVault(new BN(FUND_VECT))
+      .accounts({
+        saleState,
+        authority: saleAuthority.publicKey,
+        adminVectAccount: authorityVectAccount,
+        vectVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([saleAuthority])
+      .rpc();
+  });
+
+  it("Buyer claims the whole vesting once it has vested", async () => {
+    await buy();
+    const before = await program.account.vesting.fetch(vesting);
+    await waitForClock(before.startTime.toNumber() + CLIFF_DURATION + VESTING_DURATION);
+
+    const tx = await claim();
+    console.log("Claim tx:", tx);
+
+    const after = await program.account.vesting.fetch(vesting);
+    assert.equal(after.claimedAmount.toNumber(), PURCHASE_VECT);
+    assert.equal(await vectBalance(buyerVectAccount), PURCHASE_VECT.toString());
+    console.log("✅ Claimed", PURCHASE_VECT, "VECT after vesting");
+  });
+
+  it("Buying again restarts a fully claimed vesting", async () => {
+    const before = await program.account.vesting.fetch(vesting);
+    await waitForClock(before.startTime.toNumber() + CLIFF_DURATION + VESTING_DURATION + 1);
+
+    const tx = await buy();
+    console.log("Top-up tx:", tx);
+
+    // The new tokens start their own schedule instead of being claimable at once
+    const after = await program.account.vesting.fetch(vesting);
+    assert.equal(after.totalVectAmount.toNumber(), PURCHASE_VECT);
+    assert.equal(after.claimedAmount.toNumber(), 0);
+    assert.isAbove(after.startTime.toNumber(), before.startTime.toNumber());
+
+    try {
+      await claim();
+      assert.fail("Should have failed - restarted vesting is before its cliff");
+    } catch (error) {
+      assert.include(error.toString(), "CliffNotReached");
+    }
+
+    await waitForClock(after.startTime.toNumber() + CLIFF_DURATION + VESTING_DURATION);
+    await claim();
+    assert.equal(await vectBalance(buyerVectAccount), (2 * PURCHASE_VECT).toString());
+    console.log("✅ Top-up vested on its own schedule");
+  });
+
+  it("Position holder claims and closes a vested position", async () => {
+    const positionMint = Keypair.generate();
+    const positionVesting = pda(
+      Buffer.from("position"),
+      saleState.toBuffer(),
+      positionMint.publicKey.toBuffer()
+    );
+    const buyerPositionAccount = await anchor.utils.token.associatedAddress({
+      mint: positionMint.publicKey,
+      owner: buyer.publicKey,
+    });
+
+    await program.methods
+      .buyPositionWithUsdc(new BN(PURCHASE_USDC))
+      .accounts({
+        saleState,
+        positionMint: positionMint.publicKey,
+        positionVesting,
+        buyer: buyer.publicKey,
+        buyerUsdcAccount,
+        buyerPositionAccount,
+        usdcTreasury,
+        vectVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer, positionMint])
+      .rpc();
+
+    const position = await program.account.vesting.fetch(positionVesting);
+    await waitForClock(position.startTime.toNumber() + CLIFF_DURATION + VESTING_DURATION);
+    const balanceBefore = BigInt(await vectBalance(buyerVectAccount));
+
+    await program.methods
+      .claimPosition()
+      .accounts({
+        saleState,
+        positionVesting,
+        positionMint: positionMint.publicKey,
+        holder: buyer.publicKey,
+        holderPositionAccount: buyerPositionAccount,
+        vectMint,
+        holderVectAccount: buyerVectAccount,
+        vectVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([buyer])
+      .rpc();
+
+    assert.equal(
+      await vectBalance(buyerVectAccount),
+      (balanceBefore + BigInt(PURCHASE_VECT)).toString()
+    );
+
+    const tx = await program.methods
+      .closePosition()
+      .accounts({
+        positionVesting,
+        positionMint: positionMint.publicKey,
+        holder: buyer.publicKey,
+        holderPositionAccount: buyerPositionAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([buyer])
+      .rpc();
+
+    console.log("Close position tx:", tx);
+
+    assert.isNull(await program.account.vesting.fetchNullable(positionVesting));
+    const mintInfo = await getMint(provider.connection, positionMint.publicKey);
+    assert.equal(mintInfo.supply.toString(), "0");
+    console.log("✅ Position claimed, NFT burned and vesting closed");
+  });
+
+  it("Grantee claims and closes a vested grant", async () => {
+    await program.methods
+      .createGrant(
+        new BN(GRANT_VECT),
+        new BN(CLIFF_DURATION),
+        new BN(VESTING_DURATION)
+      )
+      .accounts({
+        saleState,
+        grant,
+        beneficiary: teamMember.publicKey,
+        authority: saleAuthority.publicKey,
+        vectVault,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([saleAuthority])
+      .rpc();
+
+    const created = await program.account.grant.fetch(grant);
+    await waitForClock(created.startTime.toNumber() + CLIFF_DURATION + VESTING_DURATION);
+    const teamMemberVectAccount = await anchor.utils.token.associatedAddress({
+      mint: vectMint,
+      owner: teamMember.publicKey,
+    });
+
+    await program.methods
+      .claimGrant()
+      .accounts({
+        saleState,
+        grant,
+        beneficiary: teamMember.publicKey,
+        vectMint,
+        beneficiaryVectAccount: teamMemberVectAccount,
+        vectVault,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([teamMember])
+      .rpc();
+
+    assert.equal(await vectBalance(teamMemberVectAccount), GRANT_VECT.toString());
+
+    const tx = await program.methods
+      .closeGrant()
+      .accounts({
+        grant,
+        beneficiary: teamMember.publicKey,
+      })
+      .signers([teamMember])
+      .rpc();
+
+    console.log("Close grant tx:", tx);
+
+    assert.isNull(await program.account.grant.fetchNullable(grant));
+    console.log("✅ Grant claimed and closed");
+  });
+
+  it("Settled sale closes and its vestings can still be closed", async () => {
+    const authorityUsdcAccount = await createAccount(
+      provider.connection,
+      payer,
+      usdcMint,
+      saleAuthority.publicKey
+    );
+    const raised = (await getAccount(provider.connection, usdcTreasury)).amount;
+
+    await program.methods
+      .endSale()
+      .accounts({ saleState, authority: saleAuthority.publicKey })
+      .signers([saleAuthority])
+      .rpc();
+    await program.methods
+      .withdrawUsdc(new BN(raised.toString()))
+      .accounts({
+        saleState,
+        authority: saleAuthority.publicKey,
+        authorityUsdcAccount,
+        usdcTreasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([saleAuthority])
+      .rpc();
+
+    const tx = await program.methods
+      .closeSale()
+      .accounts({
+        saleState,
+        authority: saleAuthority.publicKey,
+        authorityVectAccount,
+        vectVault,
+        usdcTreasury,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([saleAuthority])
+      .rpc();
+
+    console.log("Close sale tx:", tx);
+
+    // Everything sold or granted was claimed, the rest went back
+    const unsold = FUND_VECT - 3 * PURCHASE_VECT - GRANT_VECT;
+    assert.equal(await vectBalance(authorityVectAccount), unsold.toString());
+    assert.isNull(await program.account.saleState.fetchNullable(saleState));
+
+    await program.methods
+      .closeVesting()
+      .accounts({ vesting, beneficiary: buyer.publicKey })
+      .signers([buyer])
+      .rpc();
+    assert.isNull(await program.account.vesting.fetchNullable(vesting));
+    console.log("✅ Sale settled and closed,", unsold, "VECT returned");
+  });
+});